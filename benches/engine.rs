@@ -0,0 +1,76 @@
+//! `cargo bench` runs everything; `cargo bench -- tick/` picks one group.
+//! Economies are generated by `SyntheticSpec`, so results don't depend on `data/`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use economy_sim::data::compiled::Structure;
+use economy_sim::data::synthetic::SyntheticSpec;
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::stock_market::StockMarket;
+use std::time::Duration;
+
+fn structure(spec: &SyntheticSpec) -> Structure {
+    Structure::compile(spec.generate().parse().expect("synthetic xml parses")).expect("synthetic xml compiles")
+}
+
+fn tick(c: &mut Criterion) {
+    let s = structure(&SyntheticSpec::default());
+    let mut group = c.benchmark_group("tick");
+    group.sample_size(10);
+    for households in [1_000, 10_000, 100_000] {
+        let mut sim = Simulation::new(s.clone(), 1, households);
+        group.throughput(Throughput::Elements(households as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(households), &households, |b, _| b.iter(|| sim.tick()));
+    }
+    group.finish();
+}
+
+fn compile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compile");
+    for scale in [1, 10] {
+        let spec = SyntheticSpec {
+            goods: 50 * scale,
+            needs: 100 * scale,
+            household_types: 20 * scale,
+            rules: 50 * scale,
+            stocks: 20 * scale,
+            ..SyntheticSpec::default()
+        };
+        let xml = spec.generate();
+        group.bench_with_input(BenchmarkId::new("parse", scale), &xml, |b, xml| b.iter(|| xml.parse().unwrap()));
+        let raw = xml.parse().unwrap();
+        group.bench_with_input(BenchmarkId::new("compile", scale), &raw, |b, raw| {
+            b.iter_batched(|| raw.clone(), |raw| Structure::compile(raw).unwrap(), BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+fn stock_market_adjust(c: &mut Criterion) {
+    let s = structure(&SyntheticSpec { stocks: 500, ..SyntheticSpec::default() });
+    let mut sm = StockMarket::new(&s);
+    c.bench_function("stock_market_adjust/500", |b| {
+        b.iter(|| {
+            sm.reset();
+            sm.adjust(black_box(&s));
+        })
+    });
+}
+
+fn headless_run(c: &mut Criterion) {
+    let s = structure(&SyntheticSpec::default());
+    let mut group = c.benchmark_group("headless");
+    group.sample_size(10).measurement_time(Duration::from_secs(30));
+    group.bench_function("10k_ticks_1k_households", |b| {
+        b.iter(|| {
+            let mut sim = Simulation::new(s.clone(), 1, 1_000);
+            for _ in 0..10_000 {
+                sim.tick();
+            }
+            sim.state_hash()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tick, compile, stock_market_adjust, headless_run);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+//! Embeds the engine without the TUI and prints a metrics row every 100 ticks.
+//!
+//! cargo run --release --example metrics -- [config.toml]
+
+use economy_sim::{data, Config, EconomyMetrics, Simulation};
+
+fn main() -> anyhow::Result<()> {
+    let path = std::env::args().nth(1);
+    let cfg = Config::load(path.as_deref())?;
+    let structure = data::load_and_compile(&cfg)?;
+    let mut sim = Simulation::new(structure, cfg.seed, cfg.start_households);
+
+    println!("tick,households,total_cash,avg_utility,avg_good_price,cash_gini");
+    for _ in 0..10 {
+        for _ in 0..100 {
+            sim.tick();
+        }
+        let m = EconomyMetrics::compute(&sim);
+        println!(
+            "{},{},{:.2},{:.4},{:.4},{:.4}",
+            m.tick, m.households, m.total_cash, m.avg_utility, m.avg_good_price, m.cash_gini
+        );
+    }
+    Ok(())
+}
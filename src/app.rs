@@ -1,14 +1,17 @@
 // src/app.rs
 use crate::config::Config;
-use crate::data::compiled::Structure;
-use crate::simulation::{engine::Simulation, economy::EconomyMetrics};
+use crate::data::{compiled::Structure, xml};
+use crate::replay::{Input, ReplayLog};
+use crate::simulation::{engine::Simulation, economy::EconomyMetrics, household::HouseholdId};
+use crate::ui::browser::{Editing, HouseholdBrowser};
 use crossterm::event::{Event, KeyCode, KeyEvent};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum View {
     Overview,
     Households,
     Goods,
+    Production,
     Stocks,
 }
 
@@ -18,9 +21,13 @@ pub struct App {
     pub sim: Simulation,
     pub paused: bool,
     pub view: View,
-    pub selected_household: usize,
+    /// Kept in step with the browser's cursor. Looked up by id, as bankrupt
+    /// households may be removed and the rows after them shift.
+    pub selected_household: HouseholdId,
+    pub browser: HouseholdBrowser,
     pub derived: EconomyMetrics,
     pub last_action: String,
+    pub recording: Option<ReplayLog>,
 }
 
 impl App {
@@ -31,35 +38,52 @@ impl App {
             sim,
             paused: false,
             view: View::Overview,
-            selected_household: 0,
+            selected_household: HouseholdId(0),
+            browser: HouseholdBrowser::default(),
             derived: EconomyMetrics::default(),
             last_action: String::new(),
+            recording: None,
         };
         app.recompute_metrics();
         app
     }
 
-    pub fn reset(&mut self) {
-        let structure = self.sim.structure.clone();
-        self.sim = Simulation::new(structure, self.cfg.seed, self.cfg.start_households);
-        self.selected_household = 0;
+    /// Clears the selection and status after the simulation started over.
+    fn after_reset(&mut self) {
+        self.selected_household = HouseholdId(0);
+        self.browser.invalidate();
         self.last_action.clear();
         self.recompute_metrics();
     }
 
-    pub fn recompute_metrics(&mut self) {
-        let mut total_cash = 0.0;
-        let mut total_utility = 0.0;
-        for h in &self.sim.households {
-            total_cash += h.cash;
-            total_utility += h.utility;
+    /// Reloads the data files and migrates the running simulation onto them.
+    /// Failures leave the simulation as it was and are shown in the status line.
+    pub fn reload(&mut self) {
+        if self.recording.is_some() {
+            self.last_action = "reload skipped: replays can't record data changes".to_string();
+            return;
         }
-        self.derived = EconomyMetrics {
-            tick: self.sim.tick,
-            households: self.sim.households.len(),
-            total_cash,
-            avg_utility: if self.sim.households.is_empty() { 0.0 } else { total_utility / self.sim.households.len() as f64 },
+        let result = xml::load_all_with_conflicts(&self.cfg).and_then(|(raw, conflicts)| {
+            let report = self.sim.migrate(Structure::compile(raw)?)?;
+            Ok((report, conflicts.len()))
+        });
+        self.last_action = match result {
+            Ok((report, 0)) => format!("reloaded data: {}", report),
+            Ok((report, n)) => format!("reloaded data: {}; {} mod conflicts", report, n),
+            Err(e) => format!("reload failed: {:#}", e).replace('\n', " "),
         };
+        self.browser.invalidate();
+        self.recompute_metrics();
+    }
+
+    /// Points the simulation's history recording at the selected household.
+    pub fn inspect_selected(&mut self) {
+        let id = self.sim.households.position(self.selected_household).map(|_| self.selected_household);
+        self.sim.inspect(id);
+    }
+
+    pub fn recompute_metrics(&mut self) {
+        self.derived = EconomyMetrics::compute(&self.sim);
     }
 
     pub fn on_event(&mut self, ev: Event) -> anyhow::Result<bool> {
@@ -70,35 +94,79 @@ impl App {
     }
 
     fn on_key(&mut self, code: KeyCode) -> bool {
-        match code {
-            KeyCode::Char('q') => return true,
-            KeyCode::Char('p') => self.paused = !self.paused,
-            KeyCode::Char('.') => {
-                if self.paused {
-                    self.sim.tick();
-                    self.recompute_metrics();
+        if self.view == View::Households {
+            if self.browser.editing.is_some() {
+                self.browser.on_edit_key(code, &self.sim, &mut self.selected_household);
+                return false;
+            }
+            let page = self.browser.page.max(1) as isize;
+            match code {
+                KeyCode::PageUp => return self.browse(-page),
+                KeyCode::PageDown => return self.browse(page),
+                KeyCode::Home => return self.browse(isize::MIN),
+                KeyCode::End => return self.browse(isize::MAX),
+                KeyCode::Char('s') => {
+                    self.browser.query.sort = self.browser.query.sort.next();
+                    self.browser.invalidate();
+                    return false;
+                }
+                KeyCode::Char('S') => {
+                    self.browser.query.descending = !self.browser.query.descending;
+                    self.browser.invalidate();
+                    return false;
                 }
+                KeyCode::Char('f') => {
+                    self.browser.start(Editing::Filter);
+                    return false;
+                }
+                KeyCode::Char('/') => {
+                    self.browser.start(Editing::Search);
+                    return false;
+                }
+                _ => {}
             }
-            KeyCode::Char('r') => self.reset(),
+        }
+        match code {
+            KeyCode::Char('q') => return true,
+            KeyCode::Char('p') => self.apply(if self.paused { Input::Resume } else { Input::Pause }),
+            KeyCode::Char('.') if self.paused => self.apply(Input::Step),
+            KeyCode::Char('r') => self.apply(Input::Reset),
             KeyCode::Left => self.prev_view(),
             KeyCode::Right => self.next_view(),
             KeyCode::Up => self.select_prev(),
             KeyCode::Down => self.select_next(),
-            KeyCode::Char('x') => {
-                self.sim.force_sell_all_stocks();
-                self.last_action = "FORCE SELL ALL STOCKS".to_string();
-            }
+            KeyCode::Char('x') => self.apply(Input::ForceSell),
+            KeyCode::Char('l') => self.reload(),
             _ => {}
         }
         false
     }
 
+    /// Applies a state-changing input, recording it first when a replay is being captured.
+    pub fn apply(&mut self, input: Input) {
+        if let Some(log) = &mut self.recording {
+            log.push(self.sim.tick, input, self.sim.state_hash());
+        }
+        if input == Input::Step {
+            self.inspect_selected();
+        }
+        input.apply(&mut self.sim, self.cfg.seed, self.cfg.start_households);
+        match input {
+            Input::Pause => self.paused = true,
+            Input::Resume => self.paused = false,
+            Input::Step => self.recompute_metrics(),
+            Input::Reset => self.after_reset(),
+            Input::ForceSell => self.last_action = "FORCE SELL ALL STOCKS".to_string(),
+        }
+    }
+
     fn prev_view(&mut self) {
         self.view = match self.view {
             View::Overview => View::Stocks,
             View::Households => View::Overview,
             View::Goods => View::Households,
-            View::Stocks => View::Goods,
+            View::Production => View::Goods,
+            View::Stocks => View::Production,
         };
     }
 
@@ -106,20 +174,22 @@ impl App {
         self.view = match self.view {
             View::Overview => View::Households,
             View::Households => View::Goods,
-            View::Goods => View::Stocks,
+            View::Goods => View::Production,
+            View::Production => View::Stocks,
             View::Stocks => View::Overview,
         };
     }
 
+    fn browse(&mut self, delta: isize) -> bool {
+        self.browser.move_by(delta, &mut self.selected_household);
+        false
+    }
+
     fn select_prev(&mut self) {
-        if self.selected_household > 0 {
-            self.selected_household -= 1;
-        }
+        self.browser.move_by(-1, &mut self.selected_household);
     }
 
     fn select_next(&mut self) {
-        if self.selected_household + 1 < self.sim.households.len() {
-            self.selected_household += 1;
-        }
+        self.browser.move_by(1, &mut self.selected_household);
     }
 }
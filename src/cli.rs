@@ -0,0 +1,81 @@
+// src/cli.rs
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+pub struct Cli {
+    #[arg(long)]
+    pub config: Option<String>,
+    /// Mod directory to layer over the data files; repeat for several, later ones win.
+    #[arg(long = "mod")]
+    pub mods: Vec<String>,
+    /// Record every state-changing input to this replay file.
+    #[arg(long, conflicts_with = "replay")]
+    pub record: Option<String>,
+    /// Re-run a recorded replay headlessly and verify its state hashes.
+    #[arg(long)]
+    pub replay: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run headlessly and print the state hash after every tick.
+    Hash {
+        #[arg(long, default_value_t = 1000)]
+        ticks: u64,
+        /// Overrides `seed` from the config.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Overrides `start_households` from the config.
+        #[arg(long)]
+        households: Option<usize>,
+        /// Write the trail to a file instead of stdout.
+        #[arg(long)]
+        out: Option<String>,
+        /// Worker threads for household phases; 0 uses one per core. The trail is identical for any value.
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+    /// Measure ticks per second at several population sizes.
+    Perf {
+        /// Comma-separated population sizes.
+        #[arg(long, value_delimiter = ',', default_values_t = [10_000, 100_000, 1_000_000])]
+        households: Vec<usize>,
+        #[arg(long, default_value_t = 100)]
+        ticks: u64,
+        /// Worker threads for household phases; 0 uses one per core.
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+    /// Run every combination of a parameter sweep headlessly and collect metrics as CSV.
+    Sweep {
+        /// TOML file listing `ticks`, `sample_every` and the `[params]` grid.
+        #[arg(long)]
+        spec: String,
+        /// Write the CSV to a file instead of stdout.
+        #[arg(long)]
+        out: Option<String>,
+        /// Worker threads; 0 uses one per core.
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+    /// Print the goods / production / needs dependency graph.
+    Graph {
+        /// dot (Graphviz) or mermaid.
+        #[arg(long, default_value = "dot")]
+        format: String,
+        /// Write the graph to a file instead of stdout.
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Write the loaded data set (mods applied) as TOML, JSON or RON files.
+    Convert {
+        /// Target format: toml, json or ron.
+        #[arg(long)]
+        to: String,
+        /// Directory for `goods.<ext>` and the other four files.
+        #[arg(long)]
+        out: String,
+    },
+}
@@ -1,14 +1,8 @@
 // src/config.rs
-use clap::Parser;
+use crate::data::calendar::Calendar;
 use serde::Deserialize;
 use std::path::Path;
 
-#[derive(Parser, Debug)]
-pub struct Cli {
-    #[arg(long)]
-    pub config: Option<String>,
-}
-
 #[derive(Debug, Clone, Deserialize)]
 pub struct DataPaths {
     pub goods: String,
@@ -26,6 +20,19 @@ pub struct Config {
     pub start_households: usize,
     pub debug: bool,
     pub data_paths: DataPaths,
+    /// What a tick means; durations like `1d` in data files are converted with it.
+    #[serde(default)]
+    pub calendar: Calendar,
+    /// Mod directories layered over `data_paths`, in load order.
+    #[serde(default)]
+    pub mods: Vec<String>,
+    /// Reload the data files into the running simulation when they change.
+    #[serde(default = "default_watch_data")]
+    pub watch_data: bool,
+}
+
+fn default_watch_data() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -43,6 +50,9 @@ impl Default for Config {
                 production: "data/production.xml".to_string(),
                 stocks: "data/stocks.xml".to_string(),
             },
+            calendar: Calendar::default(),
+            mods: Vec::new(),
+            watch_data: true,
         }
     }
 }
@@ -57,6 +67,7 @@ impl Config {
         let mut cfg: Config = toml::from_str(&txt)?;
         cfg.tick_hz = cfg.tick_hz.max(1);
         cfg.ui_hz = cfg.ui_hz.max(1);
+        cfg.calendar.validate()?;
         Ok(cfg)
     }
 }
@@ -0,0 +1,433 @@
+use crate::data::calendar::Calendar;
+use crate::data::compiled::{CorporateActionKind, GoodCategory, IndexWeighting, InsolvencyResolution, Overflow, Structure};
+use crate::data::xml::*;
+
+/// Assembles a [`Structure`] in code. Definitions are recorded in the same raw
+/// form the XML loader produces and compiled by [`Structure::compile`], so ids
+/// are interned and references resolved exactly as for data files; unknown
+/// references surface as errors from [`StructureBuilder::build`].
+///
+/// ```
+/// use economy_sim::data::builder::StructureBuilder;
+///
+/// let mut b = StructureBuilder::new();
+/// b.good("bread", 5.0).decay_rate(0.1);
+/// b.need("eat", "bread", 1.0, 4);
+/// b.household_type("worker", 500.0).income_per_tick(1.0).need("eat").inventory("bread", 3.0);
+/// let structure = b.build()?;
+/// assert_eq!(structure.household_types[0].needs.len(), 1);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StructureBuilder {
+    raw: RawXml,
+}
+
+impl StructureBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts from already loaded definitions, e.g. to extend a data set in code.
+    pub fn from_raw(raw: RawXml) -> Self {
+        Self { raw }
+    }
+
+    pub fn good(&mut self, id: &str, base_price: f64) -> GoodBuilder<'_> {
+        self.raw.goods.goods.push(GoodXml {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            base_price,
+            decay_rate: 0.0,
+            stackable: true,
+            category: GoodCategory::Utility.as_str().to_string(),
+            quality: None,
+            services: ServicesXml::default(),
+            volume: 0.0,
+        });
+        GoodBuilder(self.raw.goods.goods.last_mut().unwrap())
+    }
+
+    pub fn need(&mut self, id: &str, good: &str, amount: f64, interval_ticks: u64) -> NeedBuilder<'_> {
+        self.raw.needs.needs.push(NeedXml {
+            id: id.to_string(),
+            good_ref: good.to_string(),
+            amount,
+            interval_ticks: interval_ticks.into(),
+            priority: 0,
+        });
+        NeedBuilder(self.raw.needs.needs.last_mut().unwrap())
+    }
+
+    pub fn household_type(&mut self, id: &str, starting_cash: f64) -> HouseholdTypeBuilder<'_> {
+        self.raw.household_types.types.push(HouseholdTypeXml {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            starting_cash,
+            starting_inventory: StartingInventoryXml::default(),
+            starting_portfolio: StartingPortfolioXml::default(),
+            needs: HouseholdNeedsXml::default(),
+            income_per_tick: 0.0,
+            max_leverage: 1.0,
+            short_probability: 0.0,
+            index_ref: None,
+            labor: 0.0,
+            storage: None,
+        });
+        HouseholdTypeBuilder(self.raw.household_types.types.last_mut().unwrap())
+    }
+
+    pub fn rule(&mut self, id: &str, ticks: u64) -> RuleBuilder<'_> {
+        self.raw.production.rules.push(ProductionRuleXml {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            ticks: ticks.into(),
+            inputs: RuleIOXml::default(),
+            outputs: RuleIOXml::default(),
+            capital: RuleIOXml::default(),
+            labor: 0.0,
+            wage: 0.0,
+            capacity: 1.0,
+            seasonality: None,
+            yield_dist: None,
+        });
+        RuleBuilder(self.raw.production.rules.last_mut().unwrap())
+    }
+
+    /// A disaster starting with probability `chance` per tick that scales output by `multiplier`.
+    pub fn shock(&mut self, id: &str, chance: f64, duration_ticks: u64, multiplier: f64) -> ShockBuilder<'_> {
+        self.raw.production.shocks.push(ShockXml {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            chance,
+            duration: duration_ticks.into(),
+            multiplier,
+            rules: ShockRulesXml::default(),
+        });
+        ShockBuilder(self.raw.production.shocks.last_mut().unwrap())
+    }
+
+    pub fn stock(&mut self, id: &str, base_price: f64, shares_outstanding: u64) -> StockBuilder<'_> {
+        self.raw.stocks.stocks.push(StockXml {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            base_price,
+            volatility: 0.0,
+            shares_outstanding,
+            sector_ref: None,
+            earnings_per_share: 0.0,
+            earnings_growth: 0.0,
+            earnings_volatility: 0.0,
+            payout_ratio: 0.0,
+            dividend_interval_ticks: 100.into(),
+            lot_size: 0.0,
+            corporate_actions: CorporateActionsXml::default(),
+        });
+        StockBuilder(self.raw.stocks.stocks.last_mut().unwrap())
+    }
+
+    pub fn quality_tier(&mut self, id: &str, display_name: &str, utility: f64) -> &mut Self {
+        self.raw.goods.quality_tiers.push(QualityTierXml { id: id.to_string(), display_name: display_name.to_string(), utility });
+        self
+    }
+
+    pub fn sector(&mut self, id: &str, display_name: &str) -> &mut Self {
+        self.raw.stocks.sectors.push(SectorXml { id: id.to_string(), display_name: display_name.to_string() });
+        self
+    }
+
+    pub fn index(&mut self, id: &str, weighting: IndexWeighting) -> IndexBuilder<'_> {
+        self.raw.stocks.indices.push(IndexXml {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            weighting: weighting.as_str().to_string(),
+            base_value: 1000.0,
+            members: IndexMembersXml::default(),
+        });
+        IndexBuilder(self.raw.stocks.indices.last_mut().unwrap())
+    }
+
+    pub fn calendar(&mut self, calendar: Calendar) -> &mut Self {
+        self.raw.calendar = calendar;
+        self
+    }
+
+    pub fn margin(&mut self, initial_margin: f64, maintenance_margin: f64, borrow_fee_per_tick: f64, loan_rate_per_tick: f64) -> &mut Self {
+        self.raw.stocks.margin = Some(MarginXml { initial_margin, maintenance_margin, borrow_fee_per_tick, loan_rate_per_tick });
+        self
+    }
+
+    /// `destitute_type` is required for [`InsolvencyResolution::Reset`].
+    pub fn insolvency(&mut self, grace_ticks: u64, resolution: InsolvencyResolution, destitute_type: Option<&str>, debt_write_down: f64) -> &mut Self {
+        self.raw.household_types.insolvency = Some(InsolvencyXml {
+            grace_ticks: grace_ticks.into(),
+            resolution: resolution.as_str().to_string(),
+            destitute_type: destitute_type.map(str::to_string),
+            debt_write_down,
+        });
+        self
+    }
+
+    pub fn firm_insolvency(&mut self, loss_intervals: u32, recovery_rate: f64) -> &mut Self {
+        self.raw.stocks.insolvency = Some(FirmInsolvencyXml { loss_intervals, recovery_rate });
+        self
+    }
+
+    pub fn raw(&self) -> &RawXml {
+        &self.raw
+    }
+
+    pub fn into_raw(self) -> RawXml {
+        self.raw
+    }
+
+    pub fn build(&self) -> anyhow::Result<Structure> {
+        Structure::compile(self.raw.clone())
+    }
+}
+
+pub struct GoodBuilder<'a>(&'a mut GoodXml);
+
+impl GoodBuilder<'_> {
+    pub fn display_name(self, name: &str) -> Self {
+        self.0.display_name = name.to_string();
+        self
+    }
+
+    pub fn decay_rate(self, rate: f64) -> Self {
+        self.0.decay_rate = rate;
+        self
+    }
+
+    pub fn stackable(self, stackable: bool) -> Self {
+        self.0.stackable = stackable;
+        self
+    }
+
+    pub fn category(self, category: GoodCategory) -> Self {
+        self.0.category = category.as_str().to_string();
+        self
+    }
+
+    pub fn quality(self, tier: &str) -> Self {
+        self.0.quality = Some(tier.to_string());
+        self
+    }
+
+    pub fn volume(self, volume: f64) -> Self {
+        self.0.volume = volume;
+        self
+    }
+
+    /// Holding this durable multiplies the decay rate of `good` by `decay_multiplier`.
+    pub fn service(self, good: &str, decay_multiplier: f64) -> Self {
+        self.0.services.services.push(ServiceXml { good_ref: good.to_string(), decay_multiplier });
+        self
+    }
+}
+
+pub struct NeedBuilder<'a>(&'a mut NeedXml);
+
+impl NeedBuilder<'_> {
+    pub fn priority(self, priority: i32) -> Self {
+        self.0.priority = priority;
+        self
+    }
+}
+
+pub struct HouseholdTypeBuilder<'a>(&'a mut HouseholdTypeXml);
+
+impl HouseholdTypeBuilder<'_> {
+    pub fn display_name(self, name: &str) -> Self {
+        self.0.display_name = name.to_string();
+        self
+    }
+
+    pub fn income_per_tick(self, income: f64) -> Self {
+        self.0.income_per_tick = income;
+        self
+    }
+
+    pub fn need(self, need: &str) -> Self {
+        self.0.needs.need_refs.push(need.to_string());
+        self
+    }
+
+    pub fn inventory(self, good: &str, qty: f64) -> Self {
+        self.0.starting_inventory.items.push(InventoryItemXml { good_ref: good.to_string(), qty });
+        self
+    }
+
+    pub fn shares(self, stock: &str, qty: f64) -> Self {
+        self.0.starting_portfolio.items.push(PortfolioItemXml { stock_ref: stock.to_string(), qty });
+        self
+    }
+
+    pub fn max_leverage(self, leverage: f64) -> Self {
+        self.0.max_leverage = leverage;
+        self
+    }
+
+    pub fn short_probability(self, p: f64) -> Self {
+        self.0.short_probability = p;
+        self
+    }
+
+    pub fn labor(self, workers: f64) -> Self {
+        self.0.labor = workers;
+        self
+    }
+
+    pub fn storage(self, capacity: f64, cost_per_volume: f64, overflow: Overflow) -> Self {
+        self.0.storage = Some(StorageXml { capacity, cost_per_volume, overflow: overflow.as_str().to_string() });
+        self
+    }
+
+    pub fn index_fund(self, index: &str) -> Self {
+        self.0.index_ref = Some(index.to_string());
+        self
+    }
+}
+
+pub struct RuleBuilder<'a>(&'a mut ProductionRuleXml);
+
+impl RuleBuilder<'_> {
+    pub fn display_name(self, name: &str) -> Self {
+        self.0.display_name = name.to_string();
+        self
+    }
+
+    pub fn input(self, good: &str, qty: f64) -> Self {
+        self.0.inputs.items.push(InventoryItemXml { good_ref: good.to_string(), qty });
+        self
+    }
+
+    pub fn output(self, good: &str, qty: f64) -> Self {
+        self.0.outputs.items.push(InventoryItemXml { good_ref: good.to_string(), qty });
+        self
+    }
+
+    /// Capital good held per run of capacity.
+    pub fn capital(self, good: &str, qty: f64) -> Self {
+        self.0.capital.items.push(InventoryItemXml { good_ref: good.to_string(), qty });
+        self
+    }
+
+    /// Workers occupied per run of capacity.
+    pub fn labor(self, workers: f64) -> Self {
+        self.0.labor = workers;
+        self
+    }
+
+    /// Paid per worker employed, every tick.
+    pub fn wage(self, wage: f64) -> Self {
+        self.0.wage = wage;
+        self
+    }
+
+    pub fn capacity(self, runs: f64) -> Self {
+        self.0.capacity = runs;
+        self
+    }
+
+    /// Output multipliers for equal phases of `period_ticks`.
+    pub fn seasonality(self, period_ticks: u64, factors: &[f64]) -> Self {
+        self.0.seasonality = Some(SeasonalityXml { period: period_ticks.into(), factors: factors.to_vec() });
+        self
+    }
+
+    pub fn yield_normal(self, mean: f64, sd: f64) -> Self {
+        self.0.yield_dist = Some(YieldXml { distribution: "normal".to_string(), mean, sd, min: 0.0, max: 1.0 });
+        self
+    }
+
+    pub fn yield_uniform(self, min: f64, max: f64) -> Self {
+        self.0.yield_dist = Some(YieldXml { distribution: "uniform".to_string(), mean: 1.0, sd: 0.0, min, max });
+        self
+    }
+}
+
+pub struct ShockBuilder<'a>(&'a mut ShockXml);
+
+impl ShockBuilder<'_> {
+    pub fn display_name(self, name: &str) -> Self {
+        self.0.display_name = name.to_string();
+        self
+    }
+
+    /// Restricts the shock to `rule`; without any it hits every rule.
+    pub fn rule(self, rule: &str) -> Self {
+        self.0.rules.rule_refs.push(rule.to_string());
+        self
+    }
+}
+
+pub struct StockBuilder<'a>(&'a mut StockXml);
+
+impl StockBuilder<'_> {
+    pub fn display_name(self, name: &str) -> Self {
+        self.0.display_name = name.to_string();
+        self
+    }
+
+    pub fn volatility(self, volatility: f64) -> Self {
+        self.0.volatility = volatility;
+        self
+    }
+
+    pub fn sector(self, sector: &str) -> Self {
+        self.0.sector_ref = Some(sector.to_string());
+        self
+    }
+
+    /// Earnings per share with their per-interval growth and random shock size.
+    pub fn earnings(self, per_share: f64, growth: f64, volatility: f64) -> Self {
+        self.0.earnings_per_share = per_share;
+        self.0.earnings_growth = growth;
+        self.0.earnings_volatility = volatility;
+        self
+    }
+
+    pub fn dividends(self, payout_ratio: f64, interval_ticks: u64) -> Self {
+        self.0.payout_ratio = payout_ratio;
+        self.0.dividend_interval_ticks = interval_ticks.into();
+        self
+    }
+
+    pub fn lot_size(self, lot_size: f64) -> Self {
+        self.0.lot_size = lot_size;
+        self
+    }
+
+    /// `amount` is the share count for issues and buybacks and the ratio for splits.
+    pub fn corporate_action(self, kind: CorporateActionKind, tick: u64, amount: f64) -> Self {
+        let (shares, ratio) = if kind == CorporateActionKind::Split { (0.0, amount) } else { (amount, 0.0) };
+        self.0.corporate_actions.actions.push(CorporateActionXml { kind: kind.as_str().to_string(), tick: tick.into(), shares, ratio });
+        self
+    }
+}
+
+pub struct IndexBuilder<'a>(&'a mut IndexXml);
+
+impl IndexBuilder<'_> {
+    pub fn display_name(self, name: &str) -> Self {
+        self.0.display_name = name.to_string();
+        self
+    }
+
+    pub fn base_value(self, value: f64) -> Self {
+        self.0.base_value = value;
+        self
+    }
+
+    /// Without any members the index covers every stock.
+    pub fn stock(self, stock: &str) -> Self {
+        self.0.members.stock_refs.push(stock.to_string());
+        self
+    }
+
+    pub fn sector(self, sector: &str) -> Self {
+        self.0.members.sector_refs.push(sector.to_string());
+        self
+    }
+}
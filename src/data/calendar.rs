@@ -0,0 +1,117 @@
+//! Simulated time. A calendar gives ticks a meaning so data files can say
+//! `1d` or `12h` instead of a tick count, and the UI can show a date.
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Calendar {
+    pub ticks_per_hour: u64,
+    pub hours_per_day: u64,
+    pub days_per_week: u64,
+    pub days_per_month: u64,
+    pub months_per_year: u64,
+    pub start_year: u64,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self { ticks_per_hour: 1, hours_per_day: 24, days_per_week: 7, days_per_month: 30, months_per_year: 12, start_year: 1 }
+    }
+}
+
+/// A point in simulated time; months and days count from 1.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Date {
+    pub year: u64,
+    pub month: u64,
+    pub day: u64,
+    pub hour: u64,
+    pub minute: u64,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Y{} M{:02} D{:02} {:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute)
+    }
+}
+
+impl Calendar {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if [self.ticks_per_hour, self.hours_per_day, self.days_per_week, self.days_per_month, self.months_per_year].contains(&0) {
+            bail!("calendar units must all be at least 1");
+        }
+        Ok(())
+    }
+
+    pub fn ticks_per_day(&self) -> u64 {
+        self.ticks_per_hour * self.hours_per_day
+    }
+
+    pub fn ticks_per_week(&self) -> u64 {
+        self.ticks_per_day() * self.days_per_week
+    }
+
+    pub fn ticks_per_month(&self) -> u64 {
+        self.ticks_per_day() * self.days_per_month
+    }
+
+    pub fn ticks_per_year(&self) -> u64 {
+        self.ticks_per_month() * self.months_per_year
+    }
+
+    fn unit_ticks(&self, unit: &str) -> Option<u64> {
+        Some(match unit {
+            "" | "t" => 1,
+            "h" => self.ticks_per_hour,
+            "d" => self.ticks_per_day(),
+            "w" => self.ticks_per_week(),
+            "mo" => self.ticks_per_month(),
+            "y" => self.ticks_per_year(),
+            _ => return None,
+        })
+    }
+
+    /// Converts a duration such as `20`, `12h`, `1d12h` or `0.5mo` to ticks,
+    /// rounding to the nearest tick. A bare number is a tick count.
+    pub fn parse_duration(&self, text: &str) -> anyhow::Result<u64> {
+        let text = text.trim();
+        if text.is_empty() {
+            bail!("empty duration");
+        }
+        let mut total = 0.0;
+        let mut rest = text;
+        while !rest.is_empty() {
+            let num_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+            let unit_len = rest[num_len..].find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(rest.len() - num_len);
+            let (num, unit) = (&rest[..num_len], rest[num_len..num_len + unit_len].trim());
+            let amount: f64 = num.parse().map_err(|_| anyhow!("invalid duration {:?}", text))?;
+            let per = self
+                .unit_ticks(unit)
+                .ok_or_else(|| anyhow!("unknown time unit {:?} in {:?}, expected t, h, d, w, mo or y", unit, text))?;
+            total += amount * per as f64;
+            rest = &rest[num_len + unit_len..];
+        }
+        Ok(total.round() as u64)
+    }
+
+    pub fn date(&self, tick: u64) -> Date {
+        let tpd = self.ticks_per_day();
+        let days = tick / tpd;
+        let in_day = tick % tpd;
+        Date {
+            year: self.start_year + days / (self.days_per_month * self.months_per_year),
+            month: days / self.days_per_month % self.months_per_year + 1,
+            day: days % self.days_per_month + 1,
+            hour: in_day / self.ticks_per_hour,
+            minute: in_day % self.ticks_per_hour * 60 / self.ticks_per_hour,
+        }
+    }
+
+    /// Zero-based month of the year, for seasonal effects.
+    pub fn month_of_year(&self, tick: u64) -> u64 {
+        tick / self.ticks_per_month() % self.months_per_year
+    }
+}
@@ -1,9 +1,48 @@
 // src/data/compiled.rs
 #![allow(dead_code)]
 
+use crate::data::calendar::Calendar;
 use crate::data::ids::*;
-use crate::data::xml::RawXml;
-use anyhow::anyhow;
+use crate::data::xml::{DurationXml, RawXml};
+use anyhow::{anyhow, Context};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GoodCategory {
+    Food,
+    Utility,
+    Durable,
+    Capital,
+}
+
+impl GoodCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GoodCategory::Food => "food",
+            GoodCategory::Utility => "utility",
+            GoodCategory::Durable => "durable",
+            GoodCategory::Capital => "capital",
+        }
+    }
+
+    /// Durable and capital goods meet needs by being held; `decay_rate` is their depreciation.
+    pub fn is_consumed(self) -> bool {
+        matches!(self, GoodCategory::Food | GoodCategory::Utility)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QualityTierDef {
+    pub id: QualityId,
+    pub display_name: String,
+    pub utility: f64,
+}
+
+/// A held durable multiplies the decay rate of `good` for its owner.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ServiceDef {
+    pub good: GoodId,
+    pub decay_multiplier: f64,
+}
 
 #[derive(Debug, Clone)]
 pub struct GoodDef {
@@ -12,6 +51,42 @@ pub struct GoodDef {
     pub base_price: f64,
     pub decay_rate: f64,
     pub stackable: bool,
+    pub category: GoodCategory,
+    pub quality: Option<QualityId>,
+    /// Utility of meeting a need with this good, from its quality tier.
+    pub utility: f64,
+    pub services: Vec<ServiceDef>,
+    /// Durables whose services slow this good's decay, with their multipliers.
+    pub serviced_by: Vec<(GoodId, f64)>,
+    pub volume: f64,
+}
+
+/// What happens to goods that do not fit a household's storage.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Overflow {
+    /// Bought and thrown away.
+    Discard,
+    /// Bought and sold straight back at the market price.
+    Sell,
+    /// Not bought.
+    Refuse,
+}
+
+impl Overflow {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Overflow::Discard => "discard",
+            Overflow::Sell => "sell",
+            Overflow::Refuse => "refuse",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StorageDef {
+    pub capacity: f64,
+    pub cost_per_volume: f64,
+    pub overflow: Overflow,
 }
 
 #[derive(Debug, Clone)]
@@ -30,8 +105,17 @@ pub struct HouseholdTypeDef {
     pub starting_cash: f64,
     pub starting_inventory: Vec<(GoodId, f64)>,
     pub starting_portfolio: Vec<(StockId, f64)>,
+    /// Sorted by priority then id, the order they are served in each tick.
     pub needs: Vec<NeedId>,
+    /// Slots in `needs` in the order the needs were declared, which is the
+    /// order the state hash lists them in.
+    pub declared_needs: Vec<usize>,
     pub income_per_tick: f64,
+    pub max_leverage: f64,
+    pub short_probability: f64,
+    pub index_fund: Option<IndexId>,
+    pub labor: f64,
+    pub storage: Option<StorageDef>,
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +125,55 @@ pub struct ProductionRuleDef {
     pub ticks: u64,
     pub inputs: Vec<(GoodId, f64)>,
     pub outputs: Vec<(GoodId, f64)>,
+    /// Capital goods held per run of capacity; they depreciate but are not used up.
+    pub capital: Vec<(GoodId, f64)>,
+    /// Workers occupied per run of capacity.
+    pub labor: f64,
+    /// Paid per worker employed, every tick.
+    pub wage: f64,
+    /// Runs completed economy-wide every `ticks`.
+    pub capacity: f64,
+    pub seasonality: Option<SeasonalityDef>,
+    pub yield_dist: YieldDist,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeasonalityDef {
+    pub period: u64,
+    pub factors: Vec<f64>,
+}
+
+impl SeasonalityDef {
+    pub fn factor(&self, tick: u64) -> f64 {
+        let phase = (tick % self.period) as u128 * self.factors.len() as u128 / self.period as u128;
+        self.factors[phase as usize]
+    }
+}
+
+/// Random yield multiplier drawn for every production run.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum YieldDist {
+    Fixed,
+    Normal { mean: f64, sd: f64 },
+    Uniform { min: f64, max: f64 },
+}
+
+/// A region-wide disaster that scales the output of its rules while active.
+#[derive(Debug, Clone)]
+pub struct ShockDef {
+    pub id: ShockId,
+    pub display_name: String,
+    pub chance: f64,
+    pub duration: u64,
+    pub multiplier: f64,
+    /// Empty means every rule.
+    pub rules: Vec<RuleId>,
+}
+
+impl ShockDef {
+    pub fn affects(&self, rule: RuleId) -> bool {
+        self.rules.is_empty() || self.rules.contains(&rule)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +183,138 @@ pub struct StockDef {
     pub base_price: f64,
     pub volatility: f64,
     pub shares_outstanding: u64,
+    pub earnings_per_share: f64,
+    pub earnings_growth: f64,
+    pub earnings_volatility: f64,
+    pub payout_ratio: f64,
+    pub dividend_interval_ticks: u64,
+    pub lot_size: f64,
+    pub corporate_actions: Vec<CorporateActionDef>,
+    pub sector: Option<SectorId>,
+}
+
+impl StockDef {
+    pub fn ipo_tick(&self) -> Option<u64> {
+        self.corporate_actions.iter().find(|a| a.kind == CorporateActionKind::Ipo).map(|a| a.tick)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CorporateActionKind {
+    Ipo,
+    SecondaryOffering,
+    Buyback,
+    Split,
+}
+
+impl CorporateActionKind {
+    /// The `kind` attribute used in XML.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CorporateActionKind::Ipo => "ipo",
+            CorporateActionKind::SecondaryOffering => "secondary",
+            CorporateActionKind::Buyback => "buyback",
+            CorporateActionKind::Split => "split",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorporateActionDef {
+    pub tick: u64,
+    pub kind: CorporateActionKind,
+    pub shares: f64,
+    pub ratio: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SectorDef {
+    pub id: SectorId,
+    pub display_name: String,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IndexWeighting {
+    Price,
+    Cap,
+    Equal,
+}
+
+impl IndexWeighting {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IndexWeighting::Price => "price",
+            IndexWeighting::Cap => "cap",
+            IndexWeighting::Equal => "equal",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexDef {
+    pub id: IndexId,
+    pub display_name: String,
+    pub weighting: IndexWeighting,
+    pub base_value: f64,
+    pub members: Vec<StockId>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InsolvencyResolution {
+    /// Liquidate and write down debt, keeping the household as it is.
+    Liquidate,
+    Remove,
+    /// Restart the household from the starting state of `destitute_type`.
+    Reset,
+}
+
+impl InsolvencyResolution {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InsolvencyResolution::Liquidate => "liquidate",
+            InsolvencyResolution::Remove => "remove",
+            InsolvencyResolution::Reset => "reset",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InsolvencyDef {
+    pub grace_ticks: u64,
+    pub resolution: InsolvencyResolution,
+    pub destitute_type: Option<HouseholdTypeId>,
+    pub debt_write_down: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FirmInsolvencyDef {
+    pub loss_intervals: u32,
+    pub recovery_rate: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarginDef {
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+    pub borrow_fee_per_tick: f64,
+    pub loan_rate_per_tick: f64,
+}
+
+impl Default for MarginDef {
+    fn default() -> Self {
+        Self {
+            initial_margin: 1.0,
+            maintenance_margin: 0.0,
+            borrow_fee_per_tick: 0.0,
+            loan_rate_per_tick: 0.0,
+        }
+    }
+}
+
+impl MarginDef {
+    pub fn max_leverage(&self) -> f64 {
+        1.0 / self.initial_margin
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,25 +323,88 @@ pub struct Structure {
     pub needs: Vec<NeedDef>,
     pub household_types: Vec<HouseholdTypeDef>,
     pub production_rules: Vec<ProductionRuleDef>,
+    pub shocks: Vec<ShockDef>,
     pub stocks: Vec<StockDef>,
+    pub sectors: Vec<SectorDef>,
+    pub indices: Vec<IndexDef>,
+    pub margin: MarginDef,
+    pub insolvency: Option<InsolvencyDef>,
+    pub firm_insolvency: Option<FirmInsolvencyDef>,
+    pub quality_tiers: Vec<QualityTierDef>,
+    /// Per good: made by one rule and used by another, so it moves between
+    /// rules instead of through the market.
+    pub intermediate: Vec<bool>,
+    /// Rules in supply chain order, upstream first.
+    pub rule_order: Vec<RuleId>,
     pub good_ids: Interner,
+    pub quality_ids: Interner,
     pub need_ids: Interner,
     pub household_type_ids: Interner,
     pub rule_ids: Interner,
+    pub shock_ids: Interner,
     pub stock_ids: Interner,
+    pub sector_ids: Interner,
+    pub index_ids: Interner,
+    pub calendar: Calendar,
+}
+
+fn ticks(calendar: &Calendar, d: &DurationXml, what: impl FnOnce() -> String) -> anyhow::Result<u64> {
+    calendar.parse_duration(&d.0).with_context(what)
+}
+
+fn intermediate_goods(goods: usize, rules: &[ProductionRuleDef]) -> Vec<bool> {
+    let mut made = vec![false; goods];
+    let mut used = vec![false; goods];
+    for r in rules {
+        for &(g, _) in &r.outputs {
+            made[g.0 as usize] = true;
+        }
+        for &(g, _) in r.inputs.iter().chain(&r.capital) {
+            used[g.0 as usize] = true;
+        }
+    }
+    made.iter().zip(&used).map(|(&m, &u)| m && u).collect()
+}
+
+/// Orders rules so every maker of an intermediate good runs before its users,
+/// keeping file order among independent rules.
+fn order_rules(rules: &[ProductionRuleDef], intermediate: &[bool], rule_ids: &Interner) -> anyhow::Result<Vec<RuleId>> {
+    let feeds = |a: &ProductionRuleDef, b: &ProductionRuleDef| {
+        a.outputs.iter().any(|&(g, _)| intermediate[g.0 as usize] && b.inputs.iter().chain(&b.capital).any(|&(u, _)| u == g))
+    };
+    let mut placed = vec![false; rules.len()];
+    let mut order = Vec::with_capacity(rules.len());
+    while order.len() < rules.len() {
+        let next = rules.iter().position(|b| !placed[b.id.0 as usize] && rules.iter().all(|a| a.id == b.id || placed[a.id.0 as usize] || !feeds(a, b)));
+        let Some(i) = next else {
+            let stuck: Vec<&str> = rules.iter().filter(|r| !placed[r.id.0 as usize]).map(|r| rule_ids.vec[r.id.0 as usize].as_str()).collect();
+            return Err(anyhow!("production rules form a cycle: {}", stuck.join(", ")));
+        };
+        placed[i] = true;
+        order.push(rules[i].id);
+    }
+    Ok(order)
 }
 
 impl Structure {
     pub fn compile(raw: RawXml) -> anyhow::Result<Self> {
+        let calendar = raw.calendar;
+        calendar.validate()?;
         let mut good_ids = Interner::default();
+        let mut quality_ids = Interner::default();
         let mut need_ids = Interner::default();
         let mut household_type_ids = Interner::default();
         let mut rule_ids = Interner::default();
         let mut stock_ids = Interner::default();
+        let mut sector_ids = Interner::default();
+        let mut index_ids = Interner::default();
 
         for g in &raw.goods.goods {
             good_ids.intern(&g.id);
         }
+        for q in &raw.goods.quality_tiers {
+            quality_ids.intern(&q.id);
+        }
         for n in &raw.needs.needs {
             need_ids.intern(&n.id);
         }
@@ -89,19 +417,74 @@ impl Structure {
         for s in &raw.stocks.stocks {
             stock_ids.intern(&s.id);
         }
+        for s in &raw.stocks.sectors {
+            sector_ids.intern(&s.id);
+        }
+        for ix in &raw.stocks.indices {
+            index_ids.intern(&ix.id);
+        }
+
+        let mut quality_tiers = vec![None; quality_ids.len()];
+        for q in raw.goods.quality_tiers {
+            if q.utility < 0.0 {
+                return Err(anyhow!("quality tier {} has negative utility", q.id));
+            }
+            let id = QualityId(quality_ids.intern(&q.id));
+            quality_tiers[id.0 as usize] = Some(QualityTierDef { id, display_name: q.display_name, utility: q.utility });
+        }
+        let quality_tiers: Vec<QualityTierDef> =
+            quality_tiers.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing quality tier slot"))).collect::<Result<_, _>>()?;
 
         let mut goods = vec![None; good_ids.len()];
         for g in raw.goods.goods {
             let id = GoodId(good_ids.intern(&g.id));
+            let category = match g.category.as_str() {
+                "food" => GoodCategory::Food,
+                "utility" => GoodCategory::Utility,
+                "durable" => GoodCategory::Durable,
+                "capital" => GoodCategory::Capital,
+                other => return Err(anyhow!("good {} has unknown category {}", g.id, other)),
+            };
+            let quality = match &g.quality {
+                Some(q) => Some(QualityId(
+                    *quality_ids.map.get(q).ok_or_else(|| anyhow!("good {} references unknown quality tier {}", g.id, q))?,
+                )),
+                None => None,
+            };
+            if g.volume < 0.0 {
+                return Err(anyhow!("good {} has negative volume", g.id));
+            }
+            if !g.services.services.is_empty() && category != GoodCategory::Durable {
+                return Err(anyhow!("good {} has services but is not durable", g.id));
+            }
+            let mut services = Vec::new();
+            for sv in &g.services.services {
+                let good = good_ids.map.get(&sv.good_ref).ok_or_else(|| anyhow!("good {} services unknown good {}", g.id, sv.good_ref))?;
+                if sv.decay_multiplier < 0.0 {
+                    return Err(anyhow!("good {} has a negative decay_multiplier for {}", g.id, sv.good_ref));
+                }
+                services.push(ServiceDef { good: GoodId(*good), decay_multiplier: sv.decay_multiplier });
+            }
             goods[id.0 as usize] = Some(GoodDef {
                 id,
                 display_name: g.display_name,
                 base_price: g.base_price,
                 decay_rate: g.decay_rate,
                 stackable: g.stackable,
+                category,
+                quality,
+                utility: quality.map_or(1.0, |q| quality_tiers[q.0 as usize].utility),
+                services,
+                serviced_by: Vec::new(),
+                volume: g.volume,
             });
         }
-        let goods: Vec<GoodDef> = goods.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing good slot"))).collect::<Result<_, _>>()?;
+        let mut goods: Vec<GoodDef> = goods.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing good slot"))).collect::<Result<_, _>>()?;
+        for d in 0..goods.len() {
+            for sv in goods[d].services.clone() {
+                goods[sv.good.0 as usize].serviced_by.push((GoodId(d as u32), sv.decay_multiplier));
+            }
+        }
 
         let mut needs = vec![None; need_ids.len()];
         for n in raw.needs.needs {
@@ -111,25 +494,156 @@ impl Structure {
                 id,
                 good: GoodId(*good_u32),
                 amount: n.amount,
-                interval_ticks: n.interval_ticks.max(1),
+                interval_ticks: ticks(&calendar, &n.interval_ticks, || format!("need {} interval_ticks", n.id))?.max(1),
                 priority: n.priority,
             });
         }
         let needs: Vec<NeedDef> = needs.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing need slot"))).collect::<Result<_, _>>()?;
 
+        let margin = match raw.stocks.margin {
+            Some(m) => {
+                if m.initial_margin <= 0.0 || m.initial_margin > 1.0 {
+                    return Err(anyhow!("margin initial_margin must be in (0, 1], got {}", m.initial_margin));
+                }
+                MarginDef {
+                    initial_margin: m.initial_margin,
+                    maintenance_margin: m.maintenance_margin.clamp(0.0, m.initial_margin),
+                    borrow_fee_per_tick: m.borrow_fee_per_tick.max(0.0),
+                    loan_rate_per_tick: m.loan_rate_per_tick.max(0.0),
+                }
+            }
+            None => MarginDef::default(),
+        };
+
+        let mut sectors = vec![None; sector_ids.len()];
+        for s in raw.stocks.sectors {
+            let id = SectorId(sector_ids.intern(&s.id));
+            sectors[id.0 as usize] = Some(SectorDef { id, display_name: s.display_name });
+        }
+        let sectors: Vec<SectorDef> = sectors.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing sector slot"))).collect::<Result<_, _>>()?;
+
         let mut stocks = vec![None; stock_ids.len()];
         for s in raw.stocks.stocks {
             let id = StockId(stock_ids.intern(&s.id));
+
+            let mut actions = Vec::new();
+            for a in s.corporate_actions.actions {
+                let tick = ticks(&calendar, &a.tick, || format!("stock {} corporate action tick", s.id))?;
+                let kind = match a.kind.as_str() {
+                    "ipo" => CorporateActionKind::Ipo,
+                    "secondary" => CorporateActionKind::SecondaryOffering,
+                    "buyback" => CorporateActionKind::Buyback,
+                    "split" => CorporateActionKind::Split,
+                    other => return Err(anyhow!("stock {} has unknown corporate action {}", s.id, other)),
+                };
+                if kind == CorporateActionKind::Split && a.ratio <= 0.0 {
+                    return Err(anyhow!("stock {} split at tick {} needs a positive ratio", s.id, tick));
+                }
+                actions.push(CorporateActionDef {
+                    tick,
+                    kind,
+                    shares: a.shares.max(0.0),
+                    ratio: a.ratio,
+                });
+            }
+            actions.sort_by_key(|a| a.tick);
+            if actions.iter().filter(|a| a.kind == CorporateActionKind::Ipo).count() > 1 {
+                return Err(anyhow!("stock {} has more than one ipo", s.id));
+            }
+
+            let sector = match &s.sector_ref {
+                Some(r) => {
+                    let sec_u32 = sector_ids.map.get(r).ok_or_else(|| anyhow!("stock {} references unknown sector {}", s.id, r))?;
+                    Some(SectorId(*sec_u32))
+                }
+                None => None,
+            };
             stocks[id.0 as usize] = Some(StockDef {
                 id,
                 display_name: s.display_name,
                 base_price: s.base_price,
                 volatility: s.volatility,
                 shares_outstanding: s.shares_outstanding,
+                earnings_per_share: s.earnings_per_share,
+                earnings_growth: s.earnings_growth,
+                earnings_volatility: s.earnings_volatility.max(0.0),
+                payout_ratio: s.payout_ratio.clamp(0.0, 1.0),
+                dividend_interval_ticks: ticks(&calendar, &s.dividend_interval_ticks, || format!("stock {} dividend_interval_ticks", s.id))?.max(1),
+                lot_size: s.lot_size.max(0.0),
+                corporate_actions: actions,
+                sector,
             });
         }
         let stocks: Vec<StockDef> = stocks.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing stock slot"))).collect::<Result<_, _>>()?;
 
+        let mut indices = vec![None; index_ids.len()];
+        for ix in raw.stocks.indices {
+            let id = IndexId(index_ids.intern(&ix.id));
+            let weighting = match ix.weighting.as_str() {
+                "price" => IndexWeighting::Price,
+                "cap" => IndexWeighting::Cap,
+                "equal" => IndexWeighting::Equal,
+                other => return Err(anyhow!("index {} has unknown weighting {}", ix.id, other)),
+            };
+
+            let mut members = Vec::new();
+            for r in &ix.members.stock_refs {
+                let sid_u32 = stock_ids.map.get(r).ok_or_else(|| anyhow!("index {} references unknown stock {}", ix.id, r))?;
+                members.push(StockId(*sid_u32));
+            }
+            for r in &ix.members.sector_refs {
+                let sec_u32 = sector_ids.map.get(r).ok_or_else(|| anyhow!("index {} references unknown sector {}", ix.id, r))?;
+                members.extend(stocks.iter().filter(|st| st.sector == Some(SectorId(*sec_u32))).map(|st| st.id));
+            }
+            if ix.members.stock_refs.is_empty() && ix.members.sector_refs.is_empty() {
+                members.extend(stocks.iter().map(|st| st.id));
+            }
+            members.sort_by_key(|m| m.0);
+            members.dedup();
+
+            indices[id.0 as usize] = Some(IndexDef {
+                id,
+                display_name: ix.display_name,
+                weighting,
+                base_value: ix.base_value,
+                members,
+            });
+        }
+        let indices: Vec<IndexDef> = indices.into_iter().map(|o| o.ok_or_else(|| anyhow!("missing index slot"))).collect::<Result<_, _>>()?;
+
+        let insolvency = match raw.household_types.insolvency {
+            Some(ins) => {
+                let resolution = match ins.resolution.as_str() {
+                    "liquidate" => InsolvencyResolution::Liquidate,
+                    "remove" => InsolvencyResolution::Remove,
+                    "reset" => InsolvencyResolution::Reset,
+                    other => return Err(anyhow!("unknown insolvency resolution {}", other)),
+                };
+                let destitute_type = match &ins.destitute_type {
+                    Some(r) => {
+                        let t_u32 = household_type_ids.map.get(r).ok_or_else(|| anyhow!("insolvency references unknown household_type {}", r))?;
+                        Some(HouseholdTypeId(*t_u32))
+                    }
+                    None => None,
+                };
+                if resolution == InsolvencyResolution::Reset && destitute_type.is_none() {
+                    return Err(anyhow!("insolvency resolution reset needs a destitute_type"));
+                }
+                Some(InsolvencyDef {
+                    grace_ticks: ticks(&calendar, &ins.grace_ticks, || "insolvency grace_ticks".to_string())?.max(1),
+                    resolution,
+                    destitute_type,
+                    debt_write_down: ins.debt_write_down.clamp(0.0, 1.0),
+                })
+            }
+            None => None,
+        };
+
+        let firm_insolvency = raw.stocks.insolvency.map(|f| FirmInsolvencyDef {
+            loss_intervals: f.loss_intervals.max(1),
+            recovery_rate: f.recovery_rate.clamp(0.0, 1.0),
+        });
+
         let mut household_types = vec![None; household_type_ids.len()];
         for h in raw.household_types.types {
             let id = HouseholdTypeId(household_type_ids.intern(&h.id));
@@ -137,9 +651,32 @@ impl Structure {
             let mut inv = Vec::new();
             for it in h.starting_inventory.items {
                 let gid_u32 = good_ids.map.get(&it.good_ref).ok_or_else(|| anyhow!("household_type {} references unknown good {}", h.id, it.good_ref))?;
+                if !goods[*gid_u32 as usize].stackable && it.qty.fract() != 0.0 {
+                    return Err(anyhow!("household_type {} starts with a fraction of non-stackable good {}", h.id, it.good_ref));
+                }
                 inv.push((GoodId(*gid_u32), it.qty));
             }
 
+            let storage = match h.storage {
+                Some(st) => {
+                    let overflow = match st.overflow.as_str() {
+                        "discard" => Overflow::Discard,
+                        "sell" => Overflow::Sell,
+                        "refuse" => Overflow::Refuse,
+                        other => return Err(anyhow!("household_type {} has unknown overflow {}", h.id, other)),
+                    };
+                    if st.capacity < 0.0 || st.cost_per_volume < 0.0 {
+                        return Err(anyhow!("household_type {} storage capacity and cost must not be negative", h.id));
+                    }
+                    let used: f64 = inv.iter().map(|&(g, q)| q * goods[g.0 as usize].volume).sum();
+                    if used > st.capacity {
+                        return Err(anyhow!("household_type {} starting inventory takes {} of its storage capacity {}", h.id, used, st.capacity));
+                    }
+                    Some(StorageDef { capacity: st.capacity, cost_per_volume: st.cost_per_volume, overflow })
+                }
+                None => None,
+            };
+
             let mut port = Vec::new();
             for it in h.starting_portfolio.items {
                 let sid_u32 = stock_ids.map.get(&it.stock_ref).ok_or_else(|| anyhow!("household_type {} references unknown stock {}", h.id, it.stock_ref))?;
@@ -151,6 +688,21 @@ impl Structure {
                 let nid_u32 = need_ids.map.get(&nr).ok_or_else(|| anyhow!("household_type {} references unknown need {}", h.id, nr))?;
                 nrefs.push(NeedId(*nid_u32));
             }
+            let mut served: Vec<usize> = (0..nrefs.len()).collect();
+            served.sort_by_key(|&i| (needs[nrefs[i].0 as usize].priority, nrefs[i].0));
+            let mut declared_needs = vec![0; served.len()];
+            for (slot, &i) in served.iter().enumerate() {
+                declared_needs[i] = slot;
+            }
+            let nrefs: Vec<NeedId> = served.iter().map(|&i| nrefs[i]).collect();
+
+            let index_fund = match &h.index_ref {
+                Some(r) => {
+                    let ix_u32 = index_ids.map.get(r).ok_or_else(|| anyhow!("household_type {} references unknown index {}", h.id, r))?;
+                    Some(IndexId(*ix_u32))
+                }
+                None => None,
+            };
 
             household_types[id.0 as usize] = Some(HouseholdTypeDef {
                 id,
@@ -159,7 +711,13 @@ impl Structure {
                 starting_inventory: inv,
                 starting_portfolio: port,
                 needs: nrefs,
+                declared_needs,
                 income_per_tick: h.income_per_tick,
+                max_leverage: h.max_leverage.max(1.0),
+                short_probability: h.short_probability.clamp(0.0, 1.0),
+                index_fund,
+                labor: h.labor.max(0.0),
+                storage,
             });
         }
         let household_types: Vec<HouseholdTypeDef> = household_types
@@ -183,30 +741,110 @@ impl Structure {
                 outputs.push((GoodId(*gid_u32), it.qty));
             }
 
+            let mut capital = Vec::new();
+            for it in r.capital.items {
+                let gid_u32 = good_ids.map.get(&it.good_ref).ok_or_else(|| anyhow!("rule {} capital references unknown good {}", r.id, it.good_ref))?;
+                if goods[*gid_u32 as usize].category != GoodCategory::Capital {
+                    return Err(anyhow!("rule {} capital {} is not a capital good", r.id, it.good_ref));
+                }
+                capital.push((GoodId(*gid_u32), it.qty));
+            }
+            if r.labor < 0.0 {
+                return Err(anyhow!("rule {} has negative labor", r.id));
+            }
+            if r.wage < 0.0 {
+                return Err(anyhow!("rule {} has a negative wage", r.id));
+            }
+
+            let seasonality = match r.seasonality {
+                Some(sx) => {
+                    if sx.factors.is_empty() || sx.factors.iter().any(|&f| f < 0.0) {
+                        return Err(anyhow!("rule {} seasonality needs at least one factor, none negative", r.id));
+                    }
+                    let period = ticks(&calendar, &sx.period, || format!("rule {} seasonality period", r.id))?.max(1);
+                    Some(SeasonalityDef { period, factors: sx.factors })
+                }
+                None => None,
+            };
+            let yield_dist = match r.yield_dist {
+                None => YieldDist::Fixed,
+                Some(y) => match y.distribution.as_str() {
+                    "fixed" => YieldDist::Fixed,
+                    "normal" if y.sd >= 0.0 => YieldDist::Normal { mean: y.mean, sd: y.sd },
+                    "uniform" if y.min <= y.max => YieldDist::Uniform { min: y.min, max: y.max },
+                    "normal" | "uniform" => return Err(anyhow!("rule {} has an invalid {} yield", r.id, y.distribution)),
+                    other => return Err(anyhow!("rule {} has unknown yield distribution {}", r.id, other)),
+                },
+            };
+
             production_rules[id.0 as usize] = Some(ProductionRuleDef {
                 id,
                 display_name: r.display_name,
-                ticks: r.ticks.max(1),
+                ticks: ticks(&calendar, &r.ticks, || format!("rule {} ticks", r.id))?.max(1),
                 inputs,
                 outputs,
+                capital,
+                labor: r.labor,
+                wage: r.wage,
+                capacity: r.capacity.max(0.0),
+                seasonality,
+                yield_dist,
             });
         }
         let production_rules: Vec<ProductionRuleDef> = production_rules
             .into_iter()
             .map(|o| o.ok_or_else(|| anyhow!("missing rule slot")))
             .collect::<Result<_, _>>()?;
+        let intermediate = intermediate_goods(goods.len(), &production_rules);
+        let rule_order = order_rules(&production_rules, &intermediate, &rule_ids)?;
+
+        let mut shock_ids = Interner::default();
+        let mut shocks = Vec::new();
+        for sx in raw.production.shocks {
+            if shock_ids.map.contains_key(&sx.id) {
+                return Err(anyhow!("shock {} is defined twice", sx.id));
+            }
+            let id = ShockId(shock_ids.intern(&sx.id));
+            let mut rules = Vec::new();
+            for r in &sx.rules.rule_refs {
+                let rid = rule_ids.map.get(r).ok_or_else(|| anyhow!("shock {} references unknown rule {}", sx.id, r))?;
+                rules.push(RuleId(*rid));
+            }
+            shocks.push(ShockDef {
+                id,
+                duration: ticks(&calendar, &sx.duration, || format!("shock {} duration", sx.id))?.max(1),
+                display_name: sx.display_name,
+                chance: sx.chance.clamp(0.0, 1.0),
+                multiplier: sx.multiplier.max(0.0),
+                rules,
+            });
+        }
 
         Ok(Self {
             goods,
             needs,
             household_types,
             production_rules,
+            shocks,
             stocks,
+            sectors,
+            indices,
+            margin,
+            insolvency,
+            firm_insolvency,
+            quality_tiers,
+            intermediate,
+            rule_order,
             good_ids,
+            quality_ids,
             need_ids,
             household_type_ids,
             rule_ids,
+            shock_ids,
             stock_ids,
+            sector_ids,
+            index_ids,
+            calendar,
         })
     }
 
@@ -217,4 +855,8 @@ impl Structure {
     pub fn stock_name(&self, id: StockId) -> &str {
         &self.stocks[id.0 as usize].display_name
     }
+
+    pub fn sector_name(&self, id: SectorId) -> &str {
+        &self.sectors[id.0 as usize].display_name
+    }
 }
@@ -0,0 +1,93 @@
+//! Data files in formats other than XML. Every format shares the schema of the
+//! raw structs in [`crate::data::xml`]: element names become keys and XML
+//! attributes such as `id` become plain keys, e.g. in JSON
+//! `{"good": [{"id": "bread", "display_name": "Bread", ...}]}`.
+
+use crate::data::mods::CATEGORY_FILES;
+use crate::data::xml::RawXml;
+use anyhow::{anyhow, bail, Context};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    Xml,
+    Toml,
+    Json,
+    Ron,
+}
+
+impl Format {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "xml" => Some(Self::Xml),
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "ron" => Some(Self::Ron),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &str) -> anyhow::Result<Self> {
+        Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_extension)
+            .ok_or_else(|| anyhow!("{}: unknown data format, expected .xml, .toml, .json or .ron", path))
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Xml => "xml",
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Ron => "ron",
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, text: &str) -> anyhow::Result<T> {
+        Ok(match self {
+            Self::Xml => quick_xml::de::from_str(text)?,
+            Self::Toml => toml::from_str(text)?,
+            Self::Json => serde_json::from_str(text)?,
+            Self::Ron => ron::from_str(text)?,
+        })
+    }
+
+    /// XML is the source format and is not written back.
+    pub fn write<T: Serialize>(self, value: &T) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Xml => bail!("writing XML data files is not supported"),
+            Self::Toml => toml::to_string_pretty(value)?,
+            Self::Json => serde_json::to_string_pretty(value)? + "\n",
+            Self::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())? + "\n",
+        })
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Writes the five data files of `raw` to `dir` as `goods.<ext>` etc.
+pub fn write_data_set(raw: &RawXml, format: Format, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    let texts = [
+        format.write(&raw.goods)?,
+        format.write(&raw.needs)?,
+        format.write(&raw.household_types)?,
+        format.write(&raw.production)?,
+        format.write(&raw.stocks)?,
+    ];
+    let mut written = Vec::new();
+    for (file, text) in CATEGORY_FILES.iter().zip(texts) {
+        let path = dir.join(Path::new(file).with_extension(format.extension()));
+        std::fs::write(&path, text).with_context(|| format!("writing {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
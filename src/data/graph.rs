@@ -0,0 +1,115 @@
+//! Dependency graph of a compiled [`Structure`]: goods are nodes, production
+//! rules are edges from their inputs (bold for capital) to their outputs, and
+//! needs are edges from household archetypes to the goods they consume.
+
+use crate::data::compiled::Structure;
+use crate::data::ids::GoodId;
+use std::fmt::Write;
+
+/// Goods consumed by a need or used by a rule that no rule produces.
+pub fn unproduced_goods(s: &Structure) -> Vec<GoodId> {
+    let mut consumed = vec![false; s.goods.len()];
+    let mut produced = vec![false; s.goods.len()];
+    for n in &s.needs {
+        consumed[n.good.0 as usize] = true;
+    }
+    for r in &s.production_rules {
+        for &(g, _) in r.inputs.iter().chain(&r.capital) {
+            consumed[g.0 as usize] = true;
+        }
+        for &(g, _) in &r.outputs {
+            produced[g.0 as usize] = true;
+        }
+    }
+    s.goods.iter().map(|g| g.id).filter(|g| consumed[g.0 as usize] && !produced[g.0 as usize]).collect()
+}
+
+/// One edge per rule input/output pair, flagged when the input is capital;
+/// rules without inputs or capital start from their own node.
+fn rule_edges(s: &Structure) -> Vec<(Option<GoodId>, GoodId, usize, bool)> {
+    let mut out = Vec::new();
+    for (ri, r) in s.production_rules.iter().enumerate() {
+        for &(o, _) in &r.outputs {
+            if r.inputs.is_empty() && r.capital.is_empty() {
+                out.push((None, o, ri, false));
+            }
+            for &(i, _) in &r.inputs {
+                out.push((Some(i), o, ri, false));
+            }
+            for &(c, _) in &r.capital {
+                out.push((Some(c), o, ri, true));
+            }
+        }
+    }
+    out
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn to_dot(s: &Structure) -> String {
+    let unproduced = unproduced_goods(s);
+    let mut out = String::from("digraph economy {\n    rankdir=LR;\n    node [fontname=\"Helvetica\"];\n");
+    for g in &s.goods {
+        let style = if unproduced.contains(&g.id) { ", color=red, fontcolor=red, penwidth=2" } else { "" };
+        writeln!(out, "    g{} [label={}, shape=ellipse{}];", g.id.0, quote(&g.display_name), style).ok();
+    }
+    for h in &s.household_types {
+        writeln!(out, "    h{} [label={}, shape=box, style=filled, fillcolor=lightgrey];", h.id.0, quote(&h.display_name)).ok();
+    }
+    for (from, to, ri, capital) in rule_edges(s) {
+        let label = quote(&s.production_rules[ri].display_name);
+        match from {
+            Some(g) if capital => writeln!(out, "    g{} -> g{} [label={}, style=bold];", g.0, to.0, label).ok(),
+            Some(g) => writeln!(out, "    g{} -> g{} [label={}];", g.0, to.0, label).ok(),
+            None => {
+                writeln!(out, "    r{} [label={}, shape=plaintext];", ri, label).ok();
+                writeln!(out, "    r{} -> g{};", ri, to.0).ok()
+            }
+        };
+    }
+    for h in &s.household_types {
+        for &nid in &h.needs {
+            let n = &s.needs[nid.0 as usize];
+            writeln!(out, "    h{} -> g{} [label={}, style=dashed];", h.id.0, n.good.0, quote(&s.need_ids.vec[nid.0 as usize])).ok();
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mermaid_label(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "#quot;"))
+}
+
+pub fn to_mermaid(s: &Structure) -> String {
+    let unproduced = unproduced_goods(s);
+    let mut out = String::from("flowchart LR\n");
+    for g in &s.goods {
+        writeln!(out, "    g{}([{}])", g.id.0, mermaid_label(&g.display_name)).ok();
+    }
+    for h in &s.household_types {
+        writeln!(out, "    h{}[{}]", h.id.0, mermaid_label(&h.display_name)).ok();
+    }
+    for (from, to, ri, capital) in rule_edges(s) {
+        let label = mermaid_label(&s.production_rules[ri].display_name);
+        match from {
+            Some(g) if capital => writeln!(out, "    g{} ==>|{}| g{}", g.0, label, to.0).ok(),
+            Some(g) => writeln!(out, "    g{} -->|{}| g{}", g.0, label, to.0).ok(),
+            None => writeln!(out, "    r{}{{{{{}}}}} --> g{}", ri, label, to.0).ok(),
+        };
+    }
+    for h in &s.household_types {
+        for &nid in &h.needs {
+            let n = &s.needs[nid.0 as usize];
+            writeln!(out, "    h{} -.->|{}| g{}", h.id.0, mermaid_label(&s.need_ids.vec[nid.0 as usize]), n.good.0).ok();
+        }
+    }
+    if !unproduced.is_empty() {
+        out.push_str("    classDef unproduced stroke:#c00,stroke-width:2px,color:#c00\n");
+        let ids: Vec<String> = unproduced.iter().map(|g| format!("g{}", g.0)).collect();
+        writeln!(out, "    class {} unproduced", ids.join(",")).ok();
+    }
+    out
+}
@@ -6,6 +6,9 @@ use std::collections::HashMap;
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct GoodId(pub u32);
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct QualityId(pub u32);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct NeedId(pub u32);
 
@@ -15,9 +18,18 @@ pub struct HouseholdTypeId(pub u32);
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct RuleId(pub u32);
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ShockId(pub u32);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct StockId(pub u32);
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SectorId(pub u32);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct IndexId(pub u32);
+
 #[derive(Debug, Default, Clone)]
 pub struct Interner {
     pub map: HashMap<String, u32>,
@@ -38,4 +50,8 @@ impl Interner {
     pub fn len(&self) -> usize {
         self.vec.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
 }
@@ -1,6 +1,13 @@
 // src/data/mod.rs
+pub mod builder;
+pub mod calendar;
 pub mod compiled;
+pub mod formats;
+pub mod graph;
 pub mod ids;
+pub mod mods;
+pub mod overrides;
+pub mod synthetic;
 pub mod xml;
 
 use crate::config::Config;
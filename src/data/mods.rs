@@ -0,0 +1,262 @@
+//! Layers mod directories over the base data files.
+//!
+//! Each mod directory may contain any of `goods.xml`, `needs.xml`,
+//! `households.xml`, `production.xml` and `stocks.xml`, with the same root
+//! element as the base file. Top-level entries are matched by element name and
+//! `id`:
+//!
+//! * an entry with a new id is added and must be complete,
+//! * an entry with an existing id overrides only the child elements it lists
+//!   (list elements such as `<needs>` or `<inputs>` are replaced whole),
+//! * `<good id="x" remove="true" />` removes an entry.
+//!
+//! Entries without an id (`<margin>`, `<insolvency>`) are merged by name. When
+//! two mods set the same field the later one wins and a [`ModConflict`] is
+//! reported.
+
+use anyhow::{anyhow, bail, Context};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+pub const CATEGORY_FILES: [&str; 5] = ["goods.xml", "needs.xml", "households.xml", "production.xml", "stocks.xml"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn set_attr(&mut self, key: &str, value: &str) {
+        match self.attrs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.attrs.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|n| match n {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    fn key(&self) -> (String, Option<String>) {
+        (self.name.clone(), self.attr("id").map(str::to_string))
+    }
+
+    pub fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (k, v) in &self.attrs {
+            out.push_str(&format!(" {}=\"{}\"", k, escape(v.as_str())));
+        }
+        if self.children.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        for child in &self.children {
+            match child {
+                Node::Element(e) => e.write(out),
+                Node::Text(t) => out.push_str(&escape(t.as_str())),
+            }
+        }
+        out.push_str(&format!("</{}>", self.name));
+    }
+}
+
+fn start_element(e: &BytesStart) -> anyhow::Result<Element> {
+    let mut attrs = Vec::new();
+    for a in e.attributes() {
+        let a = a?;
+        attrs.push((String::from_utf8_lossy(a.key.as_ref()).into_owned(), a.unescape_value()?.into_owned()));
+    }
+    Ok(Element { name: String::from_utf8_lossy(e.name().as_ref()).into_owned(), attrs, children: Vec::new() })
+}
+
+/// Parses a document into its root element, dropping comments and whitespace.
+pub fn parse(text: &str) -> anyhow::Result<Element> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => stack.push(start_element(&e)?),
+            Event::Empty(e) => {
+                let el = start_element(&e)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(el)),
+                    None => root = Some(el),
+                }
+            }
+            Event::End(_) => {
+                let el = stack.pop().ok_or_else(|| anyhow!("unbalanced closing tag"))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(el)),
+                    None => root = Some(el),
+                }
+            }
+            Event::Text(t) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Node::Text(t.unescape()?.into_owned()));
+                }
+            }
+            Event::CData(t) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Node::Text(String::from_utf8_lossy(&t).into_owned()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    root.ok_or_else(|| anyhow!("document has no root element"))
+}
+
+/// Two mods touched the same field of the same entry; `later` won.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModConflict {
+    pub file: String,
+    pub entry: String,
+    pub field: String,
+    pub earlier: String,
+    pub later: String,
+}
+
+impl fmt::Display for ModConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} set by mod {} is overridden by mod {}",
+            self.file, self.entry, self.field, self.earlier, self.later
+        )
+    }
+}
+
+/// Merges mods into one category document, remembering which mod last set each field.
+#[derive(Debug)]
+pub struct Layered {
+    file: String,
+    pub root: Element,
+    touched: HashMap<(String, String), String>,
+    pub conflicts: Vec<ModConflict>,
+}
+
+impl Layered {
+    pub fn new(file: &str, base: Element) -> Self {
+        Self { file: file.to_string(), root: base, touched: HashMap::new(), conflicts: Vec::new() }
+    }
+
+    fn touch(&mut self, entry: &str, field: &str, source: &str) {
+        let key = (entry.to_string(), field.to_string());
+        if let Some(earlier) = self.touched.get(&key) {
+            if earlier != source {
+                self.conflicts.push(ModConflict {
+                    file: self.file.clone(),
+                    entry: entry.to_string(),
+                    field: field.to_string(),
+                    earlier: earlier.clone(),
+                    later: source.to_string(),
+                });
+            }
+        }
+        self.touched.insert(key, source.to_string());
+    }
+
+    pub fn apply(&mut self, patch: Element, source: &str) -> anyhow::Result<()> {
+        if patch.name != self.root.name {
+            bail!("{} from mod {} has root <{}>, expected <{}>", self.file, source, patch.name, self.root.name);
+        }
+        for node in patch.children {
+            let Node::Element(entry) = node else { continue };
+            let key = entry.key();
+            let label = match &key.1 {
+                Some(id) => format!("{} {}", key.0, id),
+                None => key.0.clone(),
+            };
+            let pos = self.root.children.iter().position(|n| matches!(n, Node::Element(e) if e.key() == key));
+
+            if entry.attr("remove") == Some("true") {
+                let pos = pos.ok_or_else(|| anyhow!("{}: mod {} removes unknown {}", self.file, source, label))?;
+                let mut overridden: Vec<String> = self
+                    .touched
+                    .iter()
+                    .filter(|((e, f), by)| *e == label && f != "(entry)" && by.as_str() != source)
+                    .map(|((_, f), _)| f.clone())
+                    .collect();
+                overridden.sort();
+                for field in overridden {
+                    self.touch(&label, &field, source);
+                }
+                self.touch(&label, "(entry)", source);
+                self.root.children.remove(pos);
+                continue;
+            }
+
+            let Some(pos) = pos else {
+                self.touch(&label, "(entry)", source);
+                self.root.children.push(Node::Element(entry));
+                continue;
+            };
+
+            for (k, _) in &entry.attrs {
+                if k != "id" {
+                    self.touch(&label, &format!("@{}", k), source);
+                }
+            }
+            for field in entry.elements() {
+                self.touch(&label, &field.name, source);
+            }
+            let Node::Element(target) = &mut self.root.children[pos] else { unreachable!() };
+            for (k, v) in &entry.attrs {
+                target.set_attr(k, v);
+            }
+            for field in entry.elements() {
+                target.children.retain(|n| !matches!(n, Node::Element(e) if e.name == field.name));
+            }
+            target.children.extend(entry.children.into_iter().filter(|n| matches!(n, Node::Element(_))));
+        }
+        Ok(())
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        self.root.write(&mut out);
+        out
+    }
+}
+
+/// Applies every mod directory in order to the base text of one category file.
+pub fn layer(file: &str, base: &str, mods: &[String]) -> anyhow::Result<(String, Vec<ModConflict>)> {
+    if mods.is_empty() {
+        return Ok((base.to_string(), Vec::new()));
+    }
+    let mut layered = Layered::new(file, parse(base).with_context(|| format!("parsing base {}", file))?);
+    for dir in mods {
+        let path = Path::new(dir).join(file);
+        if !path.exists() {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let patch = parse(&text).with_context(|| format!("parsing {}", path.display()))?;
+        layered.apply(patch, dir)?;
+    }
+    Ok((layered.to_xml(), layered.conflicts))
+}
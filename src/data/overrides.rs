@@ -0,0 +1,174 @@
+use crate::data::xml::{MarginXml, RawXml};
+use anyhow::{anyhow, bail};
+
+/// Sets a numeric field of a raw definition addressed as `<category>.<id>.<field>`,
+/// e.g. `household_types.worker.income_per_tick` or `stocks.acme.volatility`.
+/// The single `margin`, `insolvency` and `firm_insolvency` sections are
+/// addressed as `<category>.<field>`. Corporate actions are addressed by stock
+/// and kind, e.g. `corporate_actions.acme.split_ratio`, and the value applies
+/// to every action of that kind.
+pub fn set_numeric(raw: &mut RawXml, key: &str, value: f64) -> anyhow::Result<()> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if let [category, field] = parts.as_slice() {
+        return set_section(raw, key, category, field, value);
+    }
+    let [category, id, field] = parts.as_slice() else {
+        bail!("override key {} must look like <category>.<id>.<field>", key);
+    };
+    let missing = || anyhow!("override {}: no {} with id {}", key, category, id);
+    let unknown = || anyhow!("override {}: {} has no numeric field {}", key, category, field);
+
+    match *category {
+        "goods" => {
+            let g = raw.goods.goods.iter_mut().find(|g| g.id == *id).ok_or_else(missing)?;
+            match *field {
+                "base_price" => g.base_price = value,
+                "decay_rate" => g.decay_rate = value,
+                "volume" => g.volume = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "quality_tiers" => {
+            let q = raw.goods.quality_tiers.iter_mut().find(|q| q.id == *id).ok_or_else(missing)?;
+            match *field {
+                "utility" => q.utility = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "needs" => {
+            let n = raw.needs.needs.iter_mut().find(|n| n.id == *id).ok_or_else(missing)?;
+            match *field {
+                "amount" => n.amount = value,
+                "interval_ticks" => n.interval_ticks = (value.max(0.0) as u64).into(),
+                "priority" => n.priority = value as i32,
+                _ => return Err(unknown()),
+            }
+        }
+        "household_types" => {
+            let h = raw.household_types.types.iter_mut().find(|h| h.id == *id).ok_or_else(missing)?;
+            match *field {
+                "starting_cash" => h.starting_cash = value,
+                "income_per_tick" => h.income_per_tick = value,
+                "max_leverage" => h.max_leverage = value,
+                "short_probability" => h.short_probability = value,
+                "labor" => h.labor = value,
+                "storage_capacity" | "storage_cost" => {
+                    let st = h.storage.as_mut().ok_or_else(|| anyhow!("override {}: {} has no storage", key, id))?;
+                    if *field == "storage_capacity" {
+                        st.capacity = value;
+                    } else {
+                        st.cost_per_volume = value;
+                    }
+                }
+                _ => return Err(unknown()),
+            }
+        }
+        "production" => {
+            let r = raw.production.rules.iter_mut().find(|r| r.id == *id).ok_or_else(missing)?;
+            match *field {
+                "ticks" => r.ticks = (value.max(0.0) as u64).into(),
+                "capacity" => r.capacity = value,
+                "labor" => r.labor = value,
+                "wage" => r.wage = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "shocks" => {
+            let sh = raw.production.shocks.iter_mut().find(|sh| sh.id == *id).ok_or_else(missing)?;
+            match *field {
+                "chance" => sh.chance = value,
+                "duration" => sh.duration = (value.max(0.0) as u64).into(),
+                "multiplier" => sh.multiplier = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "stocks" => {
+            let s = raw.stocks.stocks.iter_mut().find(|s| s.id == *id).ok_or_else(missing)?;
+            match *field {
+                "base_price" => s.base_price = value,
+                "volatility" => s.volatility = value,
+                "shares_outstanding" => s.shares_outstanding = value.max(0.0) as u64,
+                "earnings_per_share" => s.earnings_per_share = value,
+                "earnings_growth" => s.earnings_growth = value,
+                "earnings_volatility" => s.earnings_volatility = value,
+                "payout_ratio" => s.payout_ratio = value,
+                "dividend_interval_ticks" => s.dividend_interval_ticks = (value.max(0.0) as u64).into(),
+                "lot_size" => s.lot_size = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "corporate_actions" => {
+            let s = raw.stocks.stocks.iter_mut().find(|s| s.id == *id).ok_or_else(|| anyhow!("override {}: no stocks with id {}", key, id))?;
+            let (kind, attr) = field.rsplit_once('_').ok_or_else(unknown)?;
+            if !matches!(kind, "ipo" | "secondary" | "buyback" | "split") || !matches!(attr, "tick" | "shares" | "ratio") {
+                return Err(unknown());
+            }
+            let mut found = false;
+            for a in s.corporate_actions.actions.iter_mut().filter(|a| a.kind == kind) {
+                match attr {
+                    "tick" => a.tick = (value.max(0.0) as u64).into(),
+                    "shares" => a.shares = value,
+                    _ => a.ratio = value,
+                }
+                found = true;
+            }
+            if !found {
+                bail!("override {}: stock {} has no {} action", key, id, kind);
+            }
+        }
+        "indices" => {
+            let ix = raw.stocks.indices.iter_mut().find(|ix| ix.id == *id).ok_or_else(missing)?;
+            match *field {
+                "base_value" => ix.base_value = value,
+                _ => return Err(unknown()),
+            }
+        }
+        _ => bail!("override {}: unknown category {}", key, category),
+    }
+    Ok(())
+}
+
+fn set_section(raw: &mut RawXml, key: &str, category: &str, field: &str, value: f64) -> anyhow::Result<()> {
+    let unknown = || anyhow!("override {}: {} has no numeric field {}", key, category, field);
+    let absent = || anyhow!("override {}: the data defines no {} section", key, category);
+
+    match category {
+        "margin" => {
+            // Without a section the broker lends nothing, as in MarginDef's default.
+            let m = raw.stocks.margin.get_or_insert(MarginXml {
+                initial_margin: 1.0,
+                maintenance_margin: 0.0,
+                borrow_fee_per_tick: 0.0,
+                loan_rate_per_tick: 0.0,
+            });
+            match field {
+                "initial_margin" => m.initial_margin = value,
+                "maintenance_margin" => m.maintenance_margin = value,
+                "borrow_fee_per_tick" => m.borrow_fee_per_tick = value,
+                "loan_rate_per_tick" => m.loan_rate_per_tick = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "insolvency" => {
+            let ins = raw.household_types.insolvency.as_mut().ok_or_else(absent)?;
+            match field {
+                "grace_ticks" => ins.grace_ticks = (value.max(0.0) as u64).into(),
+                "debt_write_down" => ins.debt_write_down = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "firm_insolvency" => {
+            let fi = raw.stocks.insolvency.as_mut().ok_or_else(absent)?;
+            match field {
+                "loss_intervals" => fi.loss_intervals = value.max(0.0) as u32,
+                "recovery_rate" => fi.recovery_rate = value,
+                _ => return Err(unknown()),
+            }
+        }
+        "goods" | "quality_tiers" | "needs" | "household_types" | "production" | "shocks" | "stocks" | "corporate_actions" | "indices" => {
+            bail!("override key {} must look like <category>.<id>.<field>", key)
+        }
+        _ => bail!("override {}: unknown category {}", key, category),
+    }
+    Ok(())
+}
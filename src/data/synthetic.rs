@@ -0,0 +1,137 @@
+use crate::data::xml::RawXml;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fmt::Write;
+
+/// Shape of a generated economy. Every archetype gets `needs_per_type` needs,
+/// a starting inventory of their goods and a few shares of the first stocks;
+/// every rule turns one good into a later one, so supply chains never loop.
+#[derive(Debug, Clone)]
+pub struct SyntheticSpec {
+    pub goods: usize,
+    pub needs: usize,
+    pub household_types: usize,
+    pub needs_per_type: usize,
+    pub rules: usize,
+    pub stocks: usize,
+    pub seed: u64,
+}
+
+impl Default for SyntheticSpec {
+    fn default() -> Self {
+        Self {
+            goods: 50,
+            needs: 100,
+            household_types: 20,
+            needs_per_type: 8,
+            rules: 50,
+            stocks: 20,
+            seed: 1,
+        }
+    }
+}
+
+/// The five data files of a generated economy.
+#[derive(Debug, Clone)]
+pub struct SyntheticXml {
+    pub goods: String,
+    pub needs: String,
+    pub households: String,
+    pub production: String,
+    pub stocks: String,
+}
+
+impl SyntheticXml {
+    pub fn parse(&self) -> anyhow::Result<RawXml> {
+        RawXml::parse(&self.goods, &self.needs, &self.households, &self.production, &self.stocks)
+    }
+}
+
+impl SyntheticSpec {
+    pub fn generate(&self) -> SyntheticXml {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let goods = self.goods.max(1);
+        let needs = self.needs.max(1);
+
+        let mut g = String::from("<goods>\n");
+        for i in 0..goods {
+            writeln!(
+                g,
+                "  <good id=\"g{i}\"><display_name>Good {i}</display_name><base_price>{:.2}</base_price><decay_rate>{:.4}</decay_rate><stackable>true</stackable></good>",
+                rng.gen_range(1.0..50.0),
+                rng.gen_range(0.0..0.02),
+            )
+            .ok();
+        }
+        g.push_str("</goods>\n");
+
+        let mut n = String::from("<needs>\n");
+        for i in 0..needs {
+            writeln!(
+                n,
+                "  <need id=\"n{i}\"><good_ref>g{}</good_ref><amount>{:.2}</amount><interval_ticks>{}</interval_ticks><priority>{}</priority></need>",
+                i % goods,
+                rng.gen_range(0.5..2.0),
+                rng.gen_range(5..50),
+                rng.gen_range(0..5),
+            )
+            .ok();
+        }
+        n.push_str("</needs>\n");
+
+        let mut h = String::from("<household_types>\n");
+        for i in 0..self.household_types.max(1) {
+            write!(
+                h,
+                "  <household_type id=\"h{i}\"><display_name>Type {i}</display_name><starting_cash>{:.0}</starting_cash><starting_inventory>",
+                rng.gen_range(200.0..2000.0),
+            )
+            .ok();
+            let first = rng.gen_range(0..needs);
+            let picked: Vec<usize> = (0..self.needs_per_type.min(needs)).map(|k| (first + k) % needs).collect();
+            for &ni in &picked {
+                write!(h, "<item good_ref=\"g{}\" qty=\"{:.1}\" />", ni % goods, rng.gen_range(1.0..10.0)).ok();
+            }
+            h.push_str("</starting_inventory><starting_portfolio>");
+            for s in 0..self.stocks.min(3) {
+                write!(h, "<item stock_ref=\"s{s}\" qty=\"{}\" />", rng.gen_range(1..20)).ok();
+            }
+            h.push_str("</starting_portfolio><needs>");
+            for &ni in &picked {
+                write!(h, "<need_ref>n{ni}</need_ref>").ok();
+            }
+            writeln!(h, "</needs><income_per_tick>{:.2}</income_per_tick></household_type>", rng.gen_range(0.5..3.0)).ok();
+        }
+        h.push_str("</household_types>\n");
+
+        let mut p = String::from("<production_rules>\n");
+        for i in 0..self.rules {
+            let ticks = rng.gen_range(1..20);
+            let (a, b) = (rng.gen_range(0..goods), rng.gen_range(0..goods));
+            let inputs = if a == b { String::new() } else { format!("<item good_ref=\"g{}\" qty=\"1.0\" />", a.min(b)) };
+            writeln!(
+                p,
+                "  <rule id=\"r{i}\"><display_name>Rule {i}</display_name><ticks>{ticks}</ticks><inputs>{inputs}</inputs><outputs><item good_ref=\"g{}\" qty=\"1.0\" /></outputs></rule>",
+                a.max(b),
+            )
+            .ok();
+        }
+        p.push_str("</production_rules>\n");
+
+        let mut s = String::from("<stocks>\n");
+        for i in 0..self.stocks {
+            writeln!(
+                s,
+                "  <stock id=\"s{i}\"><display_name>Stock {i}</display_name><base_price>{:.2}</base_price><volatility>{:.3}</volatility><shares_outstanding>{}</shares_outstanding><earnings_per_share>{:.2}</earnings_per_share><payout_ratio>0.3</payout_ratio></stock>",
+                rng.gen_range(10.0..200.0),
+                rng.gen_range(0.01..0.06),
+                rng.gen_range(100_000..5_000_000),
+                rng.gen_range(0.1..5.0),
+            )
+            .ok();
+        }
+        s.push_str("</stocks>\n");
+
+        SyntheticXml { goods: g, needs: n, households: h, production: p, stocks: s }
+    }
+}
@@ -1,51 +1,180 @@
 // src/data/xml.rs
 use crate::config::Config;
-use anyhow::{anyhow, Context};
-use quick_xml::de::from_str;
-use serde::Deserialize;
-use std::path::PathBuf;
+use crate::data::calendar::Calendar;
+use crate::data::formats::Format;
+use crate::data::mods::{self, ModConflict};
+use anyhow::{anyhow, bail, Context};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Deserialize)]
+// XML attributes (`@id`, `@qty`, ...) are plain keys in the other formats.
+
+/// A tick count or a duration such as `1d` or `12h`, converted to ticks by the
+/// structure's calendar at compile time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurationXml(pub String);
+
+impl From<u64> for DurationXml {
+    fn from(ticks: u64) -> Self {
+        Self(ticks.to_string())
+    }
+}
+
+impl From<&str> for DurationXml {
+    fn from(text: &str) -> Self {
+        Self(text.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DurationXml {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl<'de> Visitor<'de> for DurationVisitor {
+            type Value = DurationXml;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a tick count or a duration such as \"1d\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<DurationXml, E> {
+                Ok(DurationXml::from(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<DurationXml, E> {
+                u64::try_from(v).map(DurationXml::from).map_err(|_| E::custom("durations can't be negative"))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<DurationXml, E> {
+                Ok(DurationXml::from(v))
+            }
+
+            // quick-xml presents element content as a map with a `$text` entry.
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<DurationXml, A::Error> {
+                let mut text = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "$text" {
+                        text = Some(map.next_value::<String>()?);
+                    } else {
+                        map.next_value::<de::IgnoredAny>()?;
+                    }
+                }
+                text.map(DurationXml).ok_or_else(|| de::Error::custom("empty duration"))
+            }
+        }
+
+        d.deserialize_any(DurationVisitor)
+    }
+}
+
+impl Serialize for DurationXml {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self.0.parse::<u64>() {
+            Ok(ticks) => s.serialize_u64(ticks),
+            Err(_) => s.serialize_str(&self.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct GoodsXml {
     #[serde(rename = "good")]
     pub goods: Vec<GoodXml>,
+    #[serde(rename = "quality_tier", default)]
+    pub quality_tiers: Vec<QualityTierXml>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GoodXml {
-    #[serde(rename = "@id")]
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
     pub id: String,
     pub display_name: String,
     pub base_price: f64,
     pub decay_rate: f64,
     pub stackable: bool,
+    /// `food`, `utility`, `durable` or `capital`.
+    #[serde(default = "default_category")]
+    pub category: String,
+    #[serde(default)]
+    pub quality: Option<String>,
+    #[serde(default)]
+    pub services: ServicesXml,
+    /// Storage space one unit takes up.
+    #[serde(default)]
+    pub volume: f64,
+}
+
+fn default_category() -> String {
+    "utility".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QualityTierXml {
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
+    pub id: String,
+    pub display_name: String,
+    /// Multiplies the utility of meeting a need with a good of this tier.
+    pub utility: f64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ServicesXml {
+    #[serde(rename = "service", default)]
+    pub services: Vec<ServiceXml>,
+}
+
+/// What a held durable does for its owner every tick.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ServiceXml {
+    #[serde(rename(deserialize = "@good_ref", serialize = "good_ref"), alias = "good_ref")]
+    pub good_ref: String,
+    #[serde(rename(deserialize = "@decay_multiplier", serialize = "decay_multiplier"), alias = "decay_multiplier")]
+    pub decay_multiplier: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct NeedsXml {
     #[serde(rename = "need")]
     pub needs: Vec<NeedXml>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NeedXml {
-    #[serde(rename = "@id")]
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
     pub id: String,
     pub good_ref: String,
     pub amount: f64,
-    pub interval_ticks: u64,
+    pub interval_ticks: DurationXml,
     pub priority: i32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct HouseholdTypesXml {
     #[serde(rename = "household_type")]
     pub types: Vec<HouseholdTypeXml>,
+    #[serde(default)]
+    pub insolvency: Option<InsolvencyXml>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InsolvencyXml {
+    pub grace_ticks: DurationXml,
+    pub resolution: String,
+    #[serde(default)]
+    pub destitute_type: Option<String>,
+    #[serde(default = "default_debt_write_down")]
+    pub debt_write_down: f64,
+}
+
+fn default_debt_write_down() -> f64 {
+    1.0
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HouseholdTypeXml {
-    #[serde(rename = "@id")]
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
     pub id: String,
     pub display_name: String,
     pub starting_cash: f64,
@@ -56,84 +185,277 @@ pub struct HouseholdTypeXml {
     #[serde(default)]
     pub needs: HouseholdNeedsXml,
     pub income_per_tick: f64,
+    #[serde(default = "default_max_leverage")]
+    pub max_leverage: f64,
+    #[serde(default)]
+    pub short_probability: f64,
+    #[serde(default)]
+    pub index_ref: Option<String>,
+    /// Workers each household of this type supplies to production rules.
+    #[serde(default)]
+    pub labor: f64,
+    /// Unlimited when absent.
+    #[serde(default)]
+    pub storage: Option<StorageXml>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StorageXml {
+    pub capacity: f64,
+    /// Charged per unit of volume held, every tick.
+    #[serde(default)]
+    pub cost_per_volume: f64,
+    /// `discard`, `sell` or `refuse`.
+    #[serde(default = "default_overflow")]
+    pub overflow: String,
+}
+
+fn default_overflow() -> String {
+    "refuse".to_string()
+}
+
+fn default_max_leverage() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct StartingInventoryXml {
     #[serde(rename = "item", default)]
     pub items: Vec<InventoryItemXml>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct StartingPortfolioXml {
     #[serde(rename = "item", default)]
     pub items: Vec<PortfolioItemXml>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct InventoryItemXml {
-    #[serde(rename = "@good_ref")]
+    #[serde(rename(deserialize = "@good_ref", serialize = "good_ref"), alias = "good_ref")]
     pub good_ref: String,
-    #[serde(rename = "@qty")]
+    #[serde(rename(deserialize = "@qty", serialize = "qty"), alias = "qty")]
     pub qty: f64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PortfolioItemXml {
-    #[serde(rename = "@stock_ref")]
+    #[serde(rename(deserialize = "@stock_ref", serialize = "stock_ref"), alias = "stock_ref")]
     pub stock_ref: String,
-    #[serde(rename = "@qty")]
+    #[serde(rename(deserialize = "@qty", serialize = "qty"), alias = "qty")]
     pub qty: f64,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct HouseholdNeedsXml {
     #[serde(rename = "need_ref", default)]
     pub need_refs: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ProductionRulesXml {
-    #[serde(rename = "rule")]
+    #[serde(rename = "rule", default)]
     pub rules: Vec<ProductionRuleXml>,
+    #[serde(rename = "shock", default)]
+    pub shocks: Vec<ShockXml>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProductionRuleXml {
-    #[serde(rename = "@id")]
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
     pub id: String,
     pub display_name: String,
-    pub ticks: u64,
+    pub ticks: DurationXml,
     #[serde(default)]
     pub inputs: RuleIOXml,
     #[serde(default)]
     pub outputs: RuleIOXml,
+    /// Capital goods each run needs on hand; they depreciate but are not used up.
+    #[serde(default)]
+    pub capital: RuleIOXml,
+    /// Workers each run occupies.
+    #[serde(default)]
+    pub labor: f64,
+    /// Paid per worker employed, every tick.
+    #[serde(default)]
+    pub wage: f64,
+    #[serde(default = "default_capacity")]
+    pub capacity: f64,
+    #[serde(default)]
+    pub seasonality: Option<SeasonalityXml>,
+    #[serde(rename = "yield", default)]
+    pub yield_dist: Option<YieldXml>,
+}
+
+fn default_capacity() -> f64 {
+    1.0
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+/// `factor`s split `period` into equal phases, e.g. four per `1y` for seasons.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SeasonalityXml {
+    #[serde(rename(deserialize = "@period", serialize = "period"), alias = "period")]
+    pub period: DurationXml,
+    #[serde(rename = "factor", default)]
+    pub factors: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct YieldXml {
+    #[serde(rename(deserialize = "@distribution", serialize = "distribution"), alias = "distribution")]
+    pub distribution: String,
+    #[serde(rename(deserialize = "@mean", serialize = "mean"), alias = "mean", default = "default_one")]
+    pub mean: f64,
+    #[serde(rename(deserialize = "@sd", serialize = "sd"), alias = "sd", default)]
+    pub sd: f64,
+    #[serde(rename(deserialize = "@min", serialize = "min"), alias = "min", default)]
+    pub min: f64,
+    #[serde(rename(deserialize = "@max", serialize = "max"), alias = "max", default = "default_one")]
+    pub max: f64,
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShockXml {
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
+    pub id: String,
+    pub display_name: String,
+    /// Probability per tick that the shock starts while it isn't active.
+    pub chance: f64,
+    pub duration: DurationXml,
+    pub multiplier: f64,
+    #[serde(default)]
+    pub rules: ShockRulesXml,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ShockRulesXml {
+    #[serde(rename = "rule_ref", default)]
+    pub rule_refs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct RuleIOXml {
     #[serde(rename = "item", default)]
     pub items: Vec<InventoryItemXml>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct StocksXml {
-    #[serde(rename = "stock")]
+    #[serde(rename = "stock", default)]
     pub stocks: Vec<StockXml>,
+    #[serde(rename = "sector", default)]
+    pub sectors: Vec<SectorXml>,
+    #[serde(rename = "index", default)]
+    pub indices: Vec<IndexXml>,
+    #[serde(default)]
+    pub margin: Option<MarginXml>,
+    #[serde(default)]
+    pub insolvency: Option<FirmInsolvencyXml>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FirmInsolvencyXml {
+    pub loss_intervals: u32,
+    #[serde(default)]
+    pub recovery_rate: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SectorXml {
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
+    pub id: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexXml {
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
+    pub id: String,
+    pub display_name: String,
+    pub weighting: String,
+    #[serde(default = "default_index_base_value")]
+    pub base_value: f64,
+    #[serde(default)]
+    pub members: IndexMembersXml,
+}
+
+fn default_index_base_value() -> f64 {
+    1000.0
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IndexMembersXml {
+    #[serde(rename = "stock_ref", default)]
+    pub stock_refs: Vec<String>,
+    #[serde(rename = "sector_ref", default)]
+    pub sector_refs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MarginXml {
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+    #[serde(default)]
+    pub borrow_fee_per_tick: f64,
+    #[serde(default)]
+    pub loan_rate_per_tick: f64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StockXml {
-    #[serde(rename = "@id")]
+    #[serde(rename(deserialize = "@id", serialize = "id"), alias = "id")]
     pub id: String,
     pub display_name: String,
     pub base_price: f64,
     pub volatility: f64,
     pub shares_outstanding: u64,
+    #[serde(default)]
+    pub sector_ref: Option<String>,
+    #[serde(default)]
+    pub earnings_per_share: f64,
+    #[serde(default)]
+    pub earnings_growth: f64,
+    #[serde(default)]
+    pub earnings_volatility: f64,
+    #[serde(default)]
+    pub payout_ratio: f64,
+    #[serde(default = "default_dividend_interval_ticks")]
+    pub dividend_interval_ticks: DurationXml,
+    #[serde(default)]
+    pub lot_size: f64,
+    #[serde(default)]
+    pub corporate_actions: CorporateActionsXml,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CorporateActionsXml {
+    #[serde(rename = "action", default)]
+    pub actions: Vec<CorporateActionXml>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorporateActionXml {
+    #[serde(rename(deserialize = "@kind", serialize = "kind"), alias = "kind")]
+    pub kind: String,
+    #[serde(rename(deserialize = "@tick", serialize = "tick"), alias = "tick")]
+    pub tick: DurationXml,
+    #[serde(rename(deserialize = "@shares", serialize = "shares"), alias = "shares", default)]
+    pub shares: f64,
+    #[serde(rename(deserialize = "@ratio", serialize = "ratio"), alias = "ratio", default)]
+    pub ratio: f64,
+}
+
+fn default_dividend_interval_ticks() -> DurationXml {
+    DurationXml::from(100)
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct RawXml {
+    pub calendar: Calendar,
     pub goods: GoodsXml,
     pub needs: NeedsXml,
     pub household_types: HouseholdTypesXml,
@@ -141,31 +463,75 @@ pub struct RawXml {
     pub stocks: StocksXml,
 }
 
-fn read_text_with_fallbacks(p: &str) -> anyhow::Result<String> {
+impl RawXml {
+    pub fn parse(goods: &str, needs: &str, households: &str, production: &str, stocks: &str) -> anyhow::Result<Self> {
+        Self::parse_as([Format::Xml; 5], [goods, needs, households, production, stocks])
+    }
+
+    /// Parses the five category files, each in its own format, in `CATEGORY_FILES` order.
+    pub fn parse_as(formats: [Format; 5], texts: [&str; 5]) -> anyhow::Result<Self> {
+        let label = |i: usize| format!("parsing {}", Path::new(mods::CATEGORY_FILES[i]).with_extension(formats[i].extension()).display());
+        Ok(Self {
+            calendar: Calendar::default(),
+            goods: formats[0].parse(texts[0]).with_context(|| label(0))?,
+            needs: formats[1].parse(texts[1]).with_context(|| label(1))?,
+            household_types: formats[2].parse(texts[2]).with_context(|| label(2))?,
+            production: formats[3].parse(texts[3]).with_context(|| label(3))?,
+            stocks: formats[4].parse(texts[4]).with_context(|| label(4))?,
+        })
+    }
+}
+
+fn resolve_path(p: &str) -> Option<PathBuf> {
     let md = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let mut candidates: Vec<PathBuf> = Vec::new();
-    candidates.push(PathBuf::from(p));
-    candidates.push(md.join(p));
-    for c in candidates {
-        if c.exists() {
-            return std::fs::read_to_string(&c).with_context(|| format!("reading {}", c.display()));
-        }
+    [PathBuf::from(p), md.join(p)].into_iter().find(|c| c.exists())
+}
+
+fn read_text_with_fallbacks(p: &str) -> anyhow::Result<String> {
+    let c = resolve_path(p).ok_or_else(|| anyhow!("file not found: {}", p))?;
+    std::fs::read_to_string(&c).with_context(|| format!("reading {}", c.display()))
+}
+
+/// Every file `load_all` would read, including mod files that don't exist yet.
+pub fn source_files(cfg: &Config) -> Vec<PathBuf> {
+    let paths = &cfg.data_paths;
+    let mut out: Vec<PathBuf> = [&paths.goods, &paths.needs, &paths.households, &paths.production, &paths.stocks]
+        .into_iter()
+        .map(|p| resolve_path(p).unwrap_or_else(|| PathBuf::from(p)))
+        .collect();
+    for dir in &cfg.mods {
+        out.extend(mods::CATEGORY_FILES.iter().map(|f| PathBuf::from(dir).join(f)));
     }
-    Err(anyhow!("file not found: {}", p))
+    out
 }
 
 pub fn load_all(cfg: &Config) -> anyhow::Result<RawXml> {
-    let goods_s = read_text_with_fallbacks(&cfg.data_paths.goods)?;
-    let needs_s = read_text_with_fallbacks(&cfg.data_paths.needs)?;
-    let hh_s = read_text_with_fallbacks(&cfg.data_paths.households)?;
-    let prod_s = read_text_with_fallbacks(&cfg.data_paths.production)?;
-    let stocks_s = read_text_with_fallbacks(&cfg.data_paths.stocks)?;
-
-    let goods: GoodsXml = from_str(&goods_s).context("parsing goods.xml")?;
-    let needs: NeedsXml = from_str(&needs_s).context("parsing needs.xml")?;
-    let household_types: HouseholdTypesXml = from_str(&hh_s).context("parsing households.xml")?;
-    let production: ProductionRulesXml = from_str(&prod_s).context("parsing production.xml")?;
-    let stocks: StocksXml = from_str(&stocks_s).context("parsing stocks.xml")?;
-
-    Ok(RawXml { goods, needs, household_types, production, stocks })
+    Ok(load_all_with_conflicts(cfg)?.0)
+}
+
+/// Loads the base files, each in the format given by its extension, and layers
+/// `cfg.mods` over the XML ones in order.
+pub fn load_all_with_conflicts(cfg: &Config) -> anyhow::Result<(RawXml, Vec<ModConflict>)> {
+    let paths = &cfg.data_paths;
+    let mut conflicts = Vec::new();
+    let mut formats = [Format::Xml; 5];
+    let mut texts = Vec::new();
+    for (i, (file, path)) in mods::CATEGORY_FILES.iter().zip([&paths.goods, &paths.needs, &paths.households, &paths.production, &paths.stocks]).enumerate() {
+        formats[i] = Format::from_path(path)?;
+        let text = read_text_with_fallbacks(path)?;
+        if formats[i] != Format::Xml {
+            if let Some(dir) = cfg.mods.iter().find(|d| Path::new(d).join(file).exists()) {
+                bail!("mod {} patches {} but the base file {} is not XML", dir, file, path);
+            }
+            texts.push(text);
+            continue;
+        }
+        let (text, c) = mods::layer(file, &text, &cfg.mods)?;
+        texts.push(text);
+        conflicts.extend(c);
+    }
+
+    let mut raw = RawXml::parse_as(formats, [&texts[0], &texts[1], &texts[2], &texts[3], &texts[4]])?;
+    raw.calendar = cfg.calendar.clone();
+    Ok((raw, conflicts))
 }
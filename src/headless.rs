@@ -0,0 +1,32 @@
+use crate::config::Config;
+use crate::data::compiled::Structure;
+use crate::simulation::engine::Simulation;
+use std::fmt::Write;
+use std::time::Instant;
+
+/// One `tick hash` line for the initial state and after every tick.
+pub fn hash_trail(cfg: &Config, structure: Structure, ticks: u64) -> String {
+    let mut sim = Simulation::new(structure, cfg.seed, cfg.start_households);
+    let mut out = String::new();
+    writeln!(out, "{} {:016x}", sim.tick, sim.state_hash()).ok();
+    for _ in 0..ticks {
+        sim.tick();
+        writeln!(out, "{} {:016x}", sim.tick, sim.state_hash()).ok();
+    }
+    out
+}
+
+/// Times `ticks` ticks for each population size, excluding setup.
+pub fn perf(cfg: &Config, structure: &Structure, sizes: &[usize], ticks: u64) -> String {
+    let mut out = format!("{:>10} {:>8} {:>10} {:>10}\n", "households", "ticks", "seconds", "ticks/s");
+    for &n in sizes {
+        let mut sim = Simulation::new(structure.clone(), cfg.seed, n);
+        let start = Instant::now();
+        for _ in 0..ticks {
+            sim.tick();
+        }
+        let secs = start.elapsed().as_secs_f64();
+        writeln!(out, "{:>10} {:>8} {:>10.3} {:>10.1}", n, ticks, secs, ticks as f64 / secs.max(1e-9)).ok();
+    }
+    out
+}
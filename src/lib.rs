@@ -0,0 +1,30 @@
+//! EconomySim's engine as a library: XML-defined structure, the deterministic
+//! tick loop and metrics, independent of the terminal frontend.
+//!
+//! ```no_run
+//! use economy_sim::{Config, EconomyMetrics, Simulation};
+//!
+//! let cfg = Config::load(None)?;
+//! let structure = economy_sim::data::load_and_compile(&cfg)?;
+//! let mut sim = Simulation::new(structure, cfg.seed, cfg.start_households);
+//! for _ in 0..1000 {
+//!     sim.tick();
+//! }
+//! let m = EconomyMetrics::compute(&sim);
+//! println!("avg utility {:.3}, cash gini {:.3}", m.avg_utility, m.cash_gini);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Build with `default-features = false` to leave out the TUI dependencies.
+
+pub mod config;
+pub mod data;
+pub mod headless;
+pub mod replay;
+pub mod simulation;
+pub mod sweep;
+
+pub use config::Config;
+pub use data::compiled::Structure;
+pub use simulation::economy::EconomyMetrics;
+pub use simulation::engine::Simulation;
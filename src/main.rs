@@ -1,26 +1,99 @@
 // src/main.rs
 mod app;
-mod config;
+mod cli;
+mod reload;
 mod util;
 
-mod data;
-mod simulation;
 mod ui;
 
-use anyhow::Context;
+use economy_sim::{config, data, headless, replay, simulation, sweep};
+
+use anyhow::{anyhow, Context};
 use clap::Parser;
-use config::{Cli, Config};
+use cli::{Cli, Command};
+use config::Config;
+use data::formats::Format;
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{io, time::Duration};
+use std::{io, path::Path, time::Duration};
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let cfg = Config::load(cli.config.as_deref()).context("loading config")?;
-    let structure = data::load_and_compile(&cfg).context("loading structure")?;
+    let mut cfg = Config::load(cli.config.as_deref()).context("loading config")?;
+    cfg.mods.extend(cli.mods.iter().cloned());
+
+    if let Some(Command::Sweep { spec, out, threads }) = &cli.command {
+        let spec = sweep::SweepSpec::load(spec)?;
+        let csv = sweep::run(&cfg, &spec, *threads)?;
+        match out {
+            Some(path) => std::fs::write(path, csv).with_context(|| format!("writing {}", path))?,
+            None => print!("{}", csv),
+        }
+        return Ok(());
+    }
+
+    let (raw, conflicts) = data::xml::load_all_with_conflicts(&cfg).context("loading data")?;
+    for c in &conflicts {
+        eprintln!("mod conflict: {}", c);
+    }
+
+    if let Some(Command::Convert { to, out }) = &cli.command {
+        let format = Format::from_extension(to)
+            .filter(|f| *f != Format::Xml)
+            .ok_or_else(|| anyhow!("unknown target format {}, expected toml, json or ron", to))?;
+        data::compiled::Structure::compile(raw.clone()).context("loading structure")?;
+        for path in data::formats::write_data_set(&raw, format, Path::new(out))? {
+            println!("wrote {}", path.display());
+        }
+        return Ok(());
+    }
+
+    let structure = data::compiled::Structure::compile(raw).context("loading structure")?;
+
+    if let Some(Command::Hash { ticks, seed, households, out, threads }) = cli.command {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()?;
+        let mut cfg = cfg;
+        cfg.seed = seed.unwrap_or(cfg.seed);
+        cfg.start_households = households.unwrap_or(cfg.start_households);
+        let trail = headless::hash_trail(&cfg, structure, ticks);
+        match out {
+            Some(path) => std::fs::write(&path, trail).with_context(|| format!("writing {}", path))?,
+            None => print!("{}", trail),
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Graph { format, out }) = &cli.command {
+        let graph = match format.as_str() {
+            "dot" => data::graph::to_dot(&structure),
+            "mermaid" => data::graph::to_mermaid(&structure),
+            other => return Err(anyhow!("unknown graph format {}, expected dot or mermaid", other)),
+        };
+        for g in data::graph::unproduced_goods(&structure) {
+            eprintln!("warning: {} is consumed but no production rule makes it", structure.good_name(g));
+        }
+        match out {
+            Some(path) => std::fs::write(path, graph).with_context(|| format!("writing {}", path))?,
+            None => print!("{}", graph),
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Perf { households, ticks, threads }) = &cli.command {
+        rayon::ThreadPoolBuilder::new().num_threads(*threads).build_global()?;
+        print!("{}", headless::perf(&cfg, &structure, households, *ticks));
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.replay {
+        let log = replay::ReplayLog::load(path)?;
+        let (tick, hash) = replay::run(structure, &log)?;
+        println!("replay ok: {} inputs, tick {}, hash {:016x}", log.entries.len(), tick, hash);
+        return Ok(());
+    }
 
     enable_raw_mode().context("enable raw mode")?;
     let mut stdout = io::stdout();
@@ -29,7 +102,7 @@ fn main() -> anyhow::Result<()> {
     let mut terminal = Terminal::new(backend).context("create terminal")?;
     terminal.clear().ok();
 
-    let res = run(terminal, cfg, structure);
+    let res = run(terminal, cfg, structure, cli.record.as_deref());
 
     disable_raw_mode().ok();
     execute!(io::stdout(), LeaveAlternateScreen).ok();
@@ -41,10 +114,15 @@ fn run(
     mut terminal: Terminal<CrosstermBackend<io::Stdout>>,
     cfg: Config,
     structure: data::compiled::Structure,
+    record: Option<&str>,
 ) -> anyhow::Result<()> {
     let mut app = app::App::new(cfg, structure);
+    if record.is_some() {
+        app.recording = Some(replay::ReplayLog::new(app.cfg.seed, app.cfg.start_households));
+    }
     let mut tick_clock = util::Ticker::new(app.cfg.tick_hz);
     let mut ui_clock = util::Ticker::new(app.cfg.ui_hz);
+    let mut watcher = app.cfg.watch_data.then(|| reload::DataWatcher::new(&app.cfg));
 
     loop {
         if let Some(ev) = util::poll_event(Duration::from_millis(1))? {
@@ -53,6 +131,11 @@ fn run(
             }
         }
 
+        if watcher.as_mut().is_some_and(|w| w.changed()) {
+            app.reload();
+        }
+
+        app.inspect_selected();
         while tick_clock.should_tick() && !app.paused {
             app.sim.tick();
             app.recompute_metrics();
@@ -63,5 +146,10 @@ fn run(
         }
     }
 
+    if let (Some(path), Some(log)) = (record, app.recording.as_mut()) {
+        log.finish(app.sim.tick, app.sim.state_hash());
+        log.save(path)?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,41 @@
+use economy_sim::config::Config;
+use economy_sim::data::xml;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls the modification times of the data and mod files.
+pub struct DataWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    next_poll: Instant,
+}
+
+impl DataWatcher {
+    pub fn new(cfg: &Config) -> Self {
+        let files = xml::source_files(cfg).into_iter().map(|p| (p.clone(), modified(&p))).collect();
+        Self { files, next_poll: Instant::now() + POLL_INTERVAL }
+    }
+
+    /// True once per batch of edits; a file appearing or disappearing counts as an edit.
+    pub fn changed(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next_poll {
+            return false;
+        }
+        self.next_poll = now + POLL_INTERVAL;
+        let mut changed = false;
+        for (path, stamp) in &mut self.files {
+            let m = modified(path);
+            if m != *stamp {
+                *stamp = m;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
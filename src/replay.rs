@@ -0,0 +1,187 @@
+//! Replay logs: the state-changing inputs of an interactive session with the
+//! state hash before each, so the session can be re-run and checked headlessly.
+
+use crate::data::compiled::Structure;
+use crate::simulation::engine::Simulation;
+use anyhow::{anyhow, bail, Context};
+use std::fmt;
+use std::path::Path;
+
+const HEADER: &str = "# EconomySim replay v1";
+
+/// A state-changing user input, as recorded in a replay file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Input {
+    Pause,
+    Resume,
+    Step,
+    Reset,
+    ForceSell,
+}
+
+impl Input {
+    fn as_str(self) -> &'static str {
+        match self {
+            Input::Pause => "pause",
+            Input::Resume => "resume",
+            Input::Step => "step",
+            Input::Reset => "reset",
+            Input::ForceSell => "force_sell",
+        }
+    }
+
+    /// Applies the input to the simulation. Pausing only affects the frontend;
+    /// a reset starts over from `seed` with `households` households.
+    pub fn apply(self, sim: &mut Simulation, seed: u64, households: usize) {
+        match self {
+            Input::Pause | Input::Resume => {}
+            Input::Step => sim.tick(),
+            Input::Reset => *sim = Simulation::new(sim.structure.clone(), seed, households),
+            Input::ForceSell => sim.force_sell_all_stocks(),
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "pause" => Input::Pause,
+            "resume" => Input::Resume,
+            "step" => Input::Step,
+            "reset" => Input::Reset,
+            "force_sell" => Input::ForceSell,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An input applied once the simulation reached `tick`, with the state hash taken just before it.
+#[derive(Debug, Clone)]
+pub struct ReplayEntry {
+    pub tick: u64,
+    pub input: Input,
+    pub hash: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayLog {
+    pub seed: u64,
+    pub households: usize,
+    pub entries: Vec<ReplayEntry>,
+    pub end: Option<(u64, u64)>,
+}
+
+impl ReplayLog {
+    pub fn new(seed: u64, households: usize) -> Self {
+        Self { seed, households, entries: Vec::new(), end: None }
+    }
+
+    pub fn push(&mut self, tick: u64, input: Input, hash: u64) {
+        self.entries.push(ReplayEntry { tick, input, hash });
+    }
+
+    pub fn finish(&mut self, tick: u64, hash: u64) {
+        self.end = Some((tick, hash));
+    }
+
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_string()).with_context(|| format!("writing replay {}", path))
+    }
+
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let txt = std::fs::read_to_string(Path::new(path)).with_context(|| format!("reading replay {}", path))?;
+        Self::parse(&txt).with_context(|| format!("parsing replay {}", path))
+    }
+
+    pub fn parse(txt: &str) -> anyhow::Result<Self> {
+        let mut seed = None;
+        let mut households = None;
+        let mut entries = Vec::new();
+        let mut end = None;
+
+        for (n, line) in txt.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let bad = || anyhow!("line {}: malformed entry {:?}", n + 1, line);
+            match parts.as_slice() {
+                ["seed", v] => seed = Some(v.parse().map_err(|_| bad())?),
+                ["households", v] => households = Some(v.parse().map_err(|_| bad())?),
+                ["end", tick, hash] => {
+                    end = Some((tick.parse().map_err(|_| bad())?, u64::from_str_radix(hash, 16).map_err(|_| bad())?));
+                }
+                [tick, input, hash] => entries.push(ReplayEntry {
+                    tick: tick.parse().map_err(|_| bad())?,
+                    input: Input::parse(input).ok_or_else(|| anyhow!("line {}: unknown input {}", n + 1, input))?,
+                    hash: u64::from_str_radix(hash, 16).map_err(|_| bad())?,
+                }),
+                _ => return Err(bad()),
+            }
+        }
+
+        Ok(Self {
+            seed: seed.ok_or_else(|| anyhow!("missing seed"))?,
+            households: households.ok_or_else(|| anyhow!("missing households"))?,
+            entries,
+            end,
+        })
+    }
+}
+
+impl fmt::Display for ReplayLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "households {}", self.households)?;
+        for e in &self.entries {
+            writeln!(f, "{} {} {:016x}", e.tick, e.input, e.hash)?;
+        }
+        if let Some((tick, hash)) = self.end {
+            writeln!(f, "end {} {:016x}", tick, hash)?;
+        }
+        Ok(())
+    }
+}
+
+fn advance_to(sim: &mut Simulation, tick: u64) -> anyhow::Result<()> {
+    if sim.tick > tick {
+        bail!("replay entry at tick {} is behind the simulation at tick {}", tick, sim.tick);
+    }
+    while sim.tick < tick {
+        sim.tick();
+    }
+    Ok(())
+}
+
+fn check(sim: &Simulation, tick: u64, expected: u64, what: &str) -> anyhow::Result<()> {
+    let got = sim.state_hash();
+    if got != expected {
+        bail!("replay diverged {} at tick {}: expected {:016x}, got {:016x}", what, tick, expected, got);
+    }
+    Ok(())
+}
+
+/// Re-runs a recorded session headlessly, failing at the first state hash that
+/// differs. Returns the final tick and state hash.
+pub fn run(structure: Structure, log: &ReplayLog) -> anyhow::Result<(u64, u64)> {
+    let mut sim = Simulation::new(structure, log.seed, log.households);
+
+    for e in &log.entries {
+        advance_to(&mut sim, e.tick)?;
+        check(&sim, e.tick, e.hash, &format!("before {}", e.input))?;
+        e.input.apply(&mut sim, log.seed, log.households);
+    }
+
+    if let Some((tick, hash)) = log.end {
+        advance_to(&mut sim, tick)?;
+        check(&sim, tick, hash, "at end of run")?;
+    }
+
+    Ok((sim.tick, sim.state_hash()))
+}
@@ -1,8 +1,60 @@
-// src/simulation/economy.rs
+use crate::simulation::engine::Simulation;
+use crate::simulation::household::market_value;
+
 #[derive(Debug, Clone, Default)]
 pub struct EconomyMetrics {
     pub tick: u64,
     pub households: usize,
     pub total_cash: f64,
     pub avg_utility: f64,
+    pub avg_good_price: f64,
+    pub stock_market_value: f64,
+    pub total_margin_debt: f64,
+    /// Gini coefficient of household cash, 0 = equal, 1 = one household holds everything.
+    pub cash_gini: f64,
+    pub margin_calls: u64,
+    pub household_bankruptcies: u64,
+    pub firm_bankruptcies: u64,
+}
+
+impl EconomyMetrics {
+    pub fn compute(sim: &Simulation) -> Self {
+        let mut total_cash = 0.0;
+        let mut total_utility = 0.0;
+        let mut stock_market_value = 0.0;
+        let mut total_margin_debt = 0.0;
+        for h in sim.households.iter() {
+            total_cash += h.cash;
+            total_utility += h.utility;
+            stock_market_value += market_value(h.portfolio, &sim.stock_market.price);
+            total_margin_debt += h.margin_debt;
+        }
+        let n = sim.households.len();
+        let prices = &sim.market.price;
+
+        Self {
+            tick: sim.tick,
+            households: n,
+            total_cash,
+            avg_utility: if n == 0 { 0.0 } else { total_utility / n as f64 },
+            avg_good_price: if prices.is_empty() { 0.0 } else { prices.iter().sum::<f64>() / prices.len() as f64 },
+            stock_market_value,
+            total_margin_debt,
+            cash_gini: gini(sim.households.cash.iter().map(|c| c.max(0.0)).collect()),
+            margin_calls: sim.margin_calls,
+            household_bankruptcies: sim.events.household_bankruptcies,
+            firm_bankruptcies: sim.events.firm_bankruptcies,
+        }
+    }
+}
+
+fn gini(mut xs: Vec<f64>) -> f64 {
+    let n = xs.len();
+    let total: f64 = xs.iter().sum();
+    if n == 0 || total <= 0.0 {
+        return 0.0;
+    }
+    xs.sort_by(f64::total_cmp);
+    let weighted: f64 = xs.iter().enumerate().map(|(i, x)| (i + 1) as f64 * x).sum();
+    (2.0 * weighted) / (n as f64 * total) - (n as f64 + 1.0) / n as f64
 }
@@ -1,37 +1,69 @@
 // src/simulation/engine.rs
-use crate::data::compiled::Structure;
-use crate::data::ids::{HouseholdTypeId, StockId};
+use crate::data::compiled::{CorporateActionKind, InsolvencyResolution, Structure};
+use crate::data::ids::{GoodId, HouseholdTypeId, StockId};
 use crate::simulation::economy::EconomyMetrics;
-use crate::simulation::household::{Household, HouseholdId, Inventory, NeedState, Portfolio};
+use crate::simulation::events::{EventKind, EventLog};
+use crate::simulation::hash::{hash_households, StateHash, StateHasher};
+use crate::simulation::household::{HouseholdId, HouseholdMut, Households};
+use crate::simulation::indices::StockIndices;
+use crate::simulation::inspect::Inspector;
+use crate::simulation::ledger::CashFlows;
 use crate::simulation::market::Market;
+use crate::simulation::production::Production;
 use crate::simulation::stock_market::StockMarket;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+/// Households per parallel work unit. Chunk partial sums are reduced in chunk
+/// order, so the result does not depend on how many threads run the chunks.
+const HOUSEHOLD_CHUNK: usize = 4096;
 
 #[derive(Debug)]
 pub struct Simulation {
     pub structure: Structure,
-    pub households: Vec<Household>,
+    pub households: Households,
     pub market: Market,
+    pub production: Production,
     pub stock_market: StockMarket,
+    pub indices: StockIndices,
     pub tick: u64,
+    seed: u64,
     rng: ChaCha8Rng,
     pub metrics: EconomyMetrics,
+    pub margin_calls: u64,
+    pub events: EventLog,
+    pub cash_flows: CashFlows,
+    /// History of one household, kept for the UI; see [`Simulation::inspect`].
+    pub inspector: Option<Inspector>,
 }
 
+/// Goods added to the inspected household's inventory by its purchases.
+type Bought = Option<Vec<(GoodId, f64)>>;
+
 impl Simulation {
     pub fn new(structure: Structure, seed: u64, start_households: usize) -> Self {
         let rng = ChaCha8Rng::seed_from_u64(seed);
         let market = Market::new(&structure);
+        let production = Production::new(&structure);
         let stock_market = StockMarket::new(&structure);
+        let indices = StockIndices::new(&structure, &stock_market);
+        let households = Households::new(&structure);
         let mut sim = Self {
             structure,
-            households: Vec::new(),
+            households,
             market,
+            production,
             stock_market,
+            indices,
             tick: 0,
+            seed,
             rng,
             metrics: EconomyMetrics::default(),
+            margin_calls: 0,
+            events: EventLog::default(),
+            cash_flows: CashFlows::default(),
+            inspector: None,
         };
         sim.spawn_households(start_households);
         sim
@@ -42,29 +74,41 @@ impl Simulation {
         for i in 0..n {
             let t = (self.rng.gen::<u32>() as usize) % tlen;
             let kind = HouseholdTypeId(t as u32);
-            let td = &self.structure.household_types[t];
-
-            let mut inv = Inventory::new(self.structure.goods.len());
-            for &(g, q) in &td.starting_inventory {
-                inv.add(g, q);
-            }
+            let port = self.allocate_portfolio(kind);
+            self.households.push(&self.structure, HouseholdId(i as u32), kind, &port);
+        }
+    }
 
-            let mut port = Portfolio::new(self.structure.stocks.len());
-            for &(sid, q) in &td.starting_portfolio {
-                port.add(sid, q);
-            }
+    /// Takes an archetype's starting shares out of the float, as far as it allows.
+    fn allocate_portfolio(&mut self, kind: HouseholdTypeId) -> Vec<(StockId, f64)> {
+        let td = &self.structure.household_types[kind.0 as usize];
+        td.starting_portfolio
+            .iter()
+            .map(|&(sid, q)| (sid, self.stock_market.allocate(&self.structure, sid, q)))
+            .collect()
+    }
 
-            let mut needs = Vec::new();
-            for &nid in &td.needs {
-                let nd = &self.structure.needs[nid.0 as usize];
-                needs.push(NeedState {
-                    need: nid,
-                    next_due_in: nd.interval_ticks,
-                    fulfilled_last: true,
-                });
-            }
+    /// Stable hash of everything that influences future ticks, including the RNG position.
+    pub fn state_hash(&self) -> u64 {
+        let mut h = StateHasher::default();
+        h.write_u64(self.tick);
+        h.write_u64(self.seed);
+        h.write_u128(self.rng.get_word_pos());
+        hash_households(&self.households, &self.structure, &mut h);
+        self.market.hash_state(&mut h);
+        self.production.hash_state(&mut h);
+        self.stock_market.hash_state(&mut h);
+        self.indices.hash_state(&mut h);
+        h.write_u64(self.margin_calls);
+        self.events.hash_state(&mut h);
+        h.finish()
+    }
 
-            self.households.push(Household::new(HouseholdId(i as u32), kind, td.starting_cash, inv, port, needs));
+    /// Starts recording the history of `household`, unless it is already being
+    /// recorded; `None` stops recording. Inspection does not change the outcome.
+    pub fn inspect(&mut self, household: Option<HouseholdId>) {
+        if self.inspector.as_ref().map(|i| i.household) != household {
+            self.inspector = household.map(Inspector::new);
         }
     }
 
@@ -72,91 +116,486 @@ impl Simulation {
         self.tick += 1;
         self.market.reset_pressures();
         self.stock_market.reset();
+        self.cash_flows = CashFlows::default();
+        self.apply_corporate_actions();
 
-        for h in &mut self.households {
-            let td = &self.structure.household_types[h.kind.0 as usize];
-            h.apply_income(td.income_per_tick);
-            h.apply_decay(&self.structure);
-            h.step_needs(&self.structure);
-        }
-
-        for h in &mut self.households {
-            let mut want = Vec::new();
-            for ns in &h.needs {
-                if ns.fulfilled_last {
-                    continue;
-                }
-                let nd = &self.structure.needs[ns.need.0 as usize];
-                want.push((nd.good, nd.amount));
-            }
-            want.sort_by_key(|(g, _)| g.0);
-
-            for (g, amount) in want {
-                let price = self.market.price[g.0 as usize];
-                let cost = price * amount;
-                self.market.note_demand(g, amount);
-                if h.cash >= cost {
-                    h.cash -= cost;
-                    h.inventory.add(g, amount);
+        let watched = self.inspector.as_mut().and_then(|i| i.begin(&self.households));
+        let watched_id = watched.map(|i| self.households.id[i]);
+        let s = &self.structure;
+        let prices = &self.market.price;
+        let partials: Vec<(Vec<f64>, Vec<f64>, CashFlows, Bought)> = self
+            .households
+            .iter_mut()
+            .split_chunks(HOUSEHOLD_CHUNK)
+            .into_par_iter()
+            .map(|chunk| {
+                let mut demand = vec![0.0; s.goods.len()];
+                let mut supply = vec![0.0; s.goods.len()];
+                let mut flows = CashFlows::default();
+                let mut bought = None;
+                for mut h in chunk {
+                    let td = &s.household_types[h.kind.0 as usize];
+                    h.apply_income(td.income_per_tick);
+                    flows.income += td.income_per_tick;
+                    flows.storage -= h.pay_storage(s);
+                    h.apply_decay(s);
+                    h.step_needs(s);
+                    let before = (Some(h.id) == watched_id).then(|| h.inventory.to_vec());
+                    flows.goods -= h.buy_unmet_needs(s, prices, &mut demand, &mut supply);
+                    if let Some(before) = before {
+                        let added = h.inventory.iter().zip(before).enumerate().filter(|(_, (&a, b))| a > b + 1e-12);
+                        bought = Some(added.map(|(g, (&a, b))| (GoodId(g as u32), a - b)).collect());
+                    }
                 }
+                (demand, supply, flows, bought)
+            })
+            .collect();
+        let mut watched_bought = Vec::new();
+        for (demand, supply, flows, bought) in partials {
+            watched_bought.extend(bought.into_iter().flatten());
+            for (i, &q) in demand.iter().enumerate() {
+                self.market.note_demand(GoodId(i as u32), q);
             }
+            for (i, &q) in supply.iter().enumerate() {
+                self.market.note_supply(GoodId(i as u32), q);
+            }
+            self.cash_flows += flows;
+        }
+        if let (Some(idx), Some(insp)) = (watched, self.inspector.as_mut()) {
+            insp.after_needs(&self.structure, &self.households, idx, self.tick, &self.market.price, &watched_bought);
         }
 
+        self.run_production();
+        self.stock_market.step_earnings(&self.structure, self.tick, &mut self.rng);
+        self.resolve_firm_failures();
+        self.pay_dividends();
+        self.charge_margin_costs();
+        if let (Some(idx), Some(insp)) = (watched, self.inspector.as_mut()) {
+            insp.before_trading(&self.households, idx);
+        }
         self.simulate_stock_trading();
+        if let (Some(idx), Some(insp)) = (watched, self.inspector.as_mut()) {
+            insp.after_trading(&self.households, idx, self.tick, &self.stock_market.price);
+        }
+        self.check_margin_calls();
+        self.resolve_insolvencies();
         self.market.adjust_prices();
         self.stock_market.adjust(&self.structure);
+        self.indices.update(&self.structure, &self.stock_market);
         self.metrics.tick = self.tick;
+        if let (Some(_), Some(insp)) = (watched, self.inspector.as_mut()) {
+            insp.finish(&self.structure, &self.households, self.tick, &self.events);
+        }
+    }
+
+    fn run_production(&mut self) {
+        for shock in self.production.step_shocks(&self.structure, self.tick, &mut self.rng) {
+            let until = self.production.shock_until[shock.0 as usize].unwrap_or(self.tick);
+            self.events.push(self.tick, EventKind::SupplyShock { shock, until });
+        }
+        let s = &self.structure;
+        let labor: f64 = self.households.kind.iter().map(|k| s.household_types[k.0 as usize].labor).sum();
+        self.production.run(s, self.tick, labor, &mut self.market, &mut self.rng);
+
+        // Everyone offering labor is employed in the same proportion, so wages
+        // are shared out by the workers each household offers.
+        if self.production.wages > 0.0 {
+            let per_worker = self.production.wages / labor;
+            for (kind, cash) in self.households.kind.iter().zip(&mut self.households.cash) {
+                let pay = s.household_types[kind.0 as usize].labor * per_worker;
+                *cash += pay;
+                self.cash_flows.wages += pay;
+            }
+        }
+    }
+
+    fn pay_dividends(&mut self) {
+        let due = &self.stock_market.dividend_due;
+        if due.iter().all(|&dps| dps <= 0.0) {
+            return;
+        }
+        let paid: Vec<f64> = self
+            .households
+            .iter_mut()
+            .split_chunks(HOUSEHOLD_CHUNK)
+            .into_par_iter()
+            .map(|chunk| {
+                let mut paid = 0.0;
+                for h in chunk {
+                    for (q, &dps) in h.portfolio.iter().zip(due) {
+                        if dps > 0.0 {
+                            *h.cash += q * dps;
+                            paid += q * dps;
+                        }
+                    }
+                }
+                paid
+            })
+            .collect();
+        self.cash_flows.dividends += paid.iter().sum::<f64>();
+    }
+
+    fn charge_margin_costs(&mut self) {
+        let m = &self.structure.margin;
+        let prices = &self.stock_market.price;
+        let fees: Vec<f64> = self
+            .households
+            .iter_mut()
+            .split_chunks(HOUSEHOLD_CHUNK)
+            .into_par_iter()
+            .map(|chunk| {
+                let mut fees = 0.0;
+                for h in chunk {
+                    if *h.margin_debt > 0.0 {
+                        *h.margin_debt *= 1.0 + m.loan_rate_per_tick;
+                    }
+                    if m.borrow_fee_per_tick > 0.0 && h.has_short() {
+                        let short_value: f64 =
+                            h.portfolio.iter().zip(prices).filter(|(q, _)| **q < 0.0).map(|(q, p)| -q * p).sum();
+                        *h.cash -= short_value * m.borrow_fee_per_tick;
+                        fees += short_value * m.borrow_fee_per_tick;
+                    }
+                }
+                fees
+            })
+            .collect();
+        self.cash_flows.borrow_fees -= fees.iter().sum::<f64>();
     }
 
     fn simulate_stock_trading(&mut self) {
         if self.structure.stocks.is_empty() {
             return;
         }
+        let broker_leverage = self.structure.margin.max_leverage();
+        let fund_weights: Vec<Vec<(StockId, f64)>> =
+            self.structure.indices.iter().map(|ix| StockIndices::weights(ix, &self.stock_market)).collect();
 
-        for h in &mut self.households {
-            let invest_budget = (h.cash - 150.0).max(0.0) * 0.01;
+        for mut h in self.households.iter_mut() {
+            let td = &self.structure.household_types[h.kind.0 as usize];
+            let leverage = td.max_leverage.min(broker_leverage);
+            let invest_budget = (*h.cash - 150.0).max(0.0) * 0.01 * leverage;
             if invest_budget <= 0.0 {
                 continue;
             }
 
-            let sid = StockId((self.rng.gen::<u32>() as usize % self.structure.stocks.len()) as u32);
+            if let Some(ix) = td.index_fund {
+                for &(sid, w) in &fund_weights[ix.0 as usize] {
+                    let p = self.stock_market.price[sid.0 as usize];
+                    let qty = (invest_budget * w / p).min(self.stock_market.available(sid));
+                    let qty = self.stock_market.round_lot(&self.structure, sid, qty);
+                    buy_long(&mut h, &mut self.stock_market, &mut self.cash_flows, sid, qty, leverage);
+                }
+                continue;
+            }
+
+            let mut rng = household_rng(self.seed, self.tick, h.id);
+            let sid = StockId((rng.gen::<u32>() as usize % self.structure.stocks.len()) as u32);
+            if !self.stock_market.listed[sid.0 as usize] {
+                continue;
+            }
             let p = self.stock_market.price[sid.0 as usize];
             if p <= 0.0 {
                 continue;
             }
+            let go_short = td.short_probability > 0.0 && rng.gen::<f64>() < td.short_probability;
 
-            let qty = (invest_budget / p).max(0.0);
-            if qty <= 0.0 {
+            let cap = if go_short { self.stock_market.lendable(sid) } else { self.stock_market.available(sid) };
+            let qty = (invest_budget / p).min(cap).max(0.0);
+            let qty = self.stock_market.round_lot(&self.structure, sid, qty);
+
+            if go_short {
+                let prices = &self.stock_market.price;
+                let headroom = h.equity(prices) * leverage - h.gross_exposure(prices);
+                let qty = self.stock_market.round_lot(&self.structure, sid, qty.min(headroom / p));
+                if qty > 0.0 {
+                    execute_trade(&mut h, &mut self.stock_market, &mut self.cash_flows, sid, -qty);
+                }
+            } else {
+                buy_long(&mut h, &mut self.stock_market, &mut self.cash_flows, sid, qty, leverage);
+            }
+        }
+    }
+
+    fn check_margin_calls(&mut self) {
+        let maintenance = self.structure.margin.maintenance_margin;
+        for mut h in self.households.iter_mut() {
+            if *h.margin_debt <= 0.0 && !h.has_short() {
                 continue;
             }
+            let prices = &self.stock_market.price;
+            let exposure = h.gross_exposure(prices);
+            if exposure <= 0.0 || h.equity(prices) >= maintenance * exposure {
+                continue;
+            }
+            close_positions(&mut h, &mut self.stock_market, &mut self.cash_flows);
+            self.margin_calls += 1;
+            self.events.push(self.tick, EventKind::MarginCall { household: h.id });
+        }
+    }
 
-            let cost = qty * p;
-            if h.cash >= cost {
-                h.cash -= cost;
-                h.portfolio.add(sid, qty);
-                self.stock_market.note_buy(sid, qty);
+    /// A household is in distress while its equity is negative or neither its cash
+    /// nor its whole net worth covers the needs it missed; after `grace_ticks` of
+    /// distress it is declared bankrupt.
+    fn resolve_insolvencies(&mut self) {
+        let Some(ins) = self.structure.insolvency.clone() else {
+            return;
+        };
+
+        let mut bankrupt = Vec::new();
+        for (idx, h) in self.households.iter_mut().enumerate() {
+            let td = &self.structure.household_types[h.kind.0 as usize];
+            let unmet_cost: f64 = td
+                .needs
+                .iter()
+                .zip(h.fulfilled_last.iter())
+                .filter(|(_, &fulfilled)| !fulfilled)
+                .map(|(nid, _)| {
+                    let nd = &self.structure.needs[nid.0 as usize];
+                    self.market.price[nd.good.0 as usize] * nd.amount
+                })
+                .sum();
+            let equity = h.equity(&self.stock_market.price);
+            if equity < 0.0 || (*h.cash < unmet_cost && equity < unmet_cost) {
+                *h.distress_ticks += 1;
+            } else {
+                *h.distress_ticks = 0;
+            }
+            if *h.distress_ticks > ins.grace_ticks {
+                bankrupt.push(idx);
             }
         }
+
+        for &idx in bankrupt.iter().rev() {
+            let mut h = self.households.get_mut(idx);
+            let cash_before = *h.cash;
+            for gd in &self.structure.goods {
+                let q = h.good(gd.id);
+                if q <= 0.0 {
+                    continue;
+                }
+                *h.cash += q * self.market.price[gd.id.0 as usize];
+                self.cash_flows.goods += q * self.market.price[gd.id.0 as usize];
+                h.remove_good(gd.id, q);
+                self.market.note_supply(gd.id, q);
+            }
+            close_positions(&mut h, &mut self.stock_market, &mut self.cash_flows);
+            let liquidated = *h.cash - cash_before;
+
+            let debt = *h.margin_debt + (-*h.cash).max(0.0);
+            let written_off = debt * ins.debt_write_down;
+            *h.margin_debt *= 1.0 - ins.debt_write_down;
+            if *h.cash < 0.0 {
+                self.cash_flows.write_offs -= *h.cash * ins.debt_write_down;
+                *h.cash *= 1.0 - ins.debt_write_down;
+            }
+            *h.distress_ticks = 0;
+            let id = h.id;
+
+            match ins.resolution {
+                InsolvencyResolution::Liquidate => {}
+                InsolvencyResolution::Remove => {
+                    // Removed together below, so the rows still to settle stay put.
+                    self.cash_flows.replaced -= self.households.cash[idx];
+                }
+                InsolvencyResolution::Reset => {
+                    if let Some(t) = ins.destitute_type {
+                        let port = self.allocate_portfolio(t);
+                        let cash_before = self.households.cash[idx];
+                        self.households.reset(idx, &self.structure, id, t, &port);
+                        self.cash_flows.replaced += self.households.cash[idx] - cash_before;
+                    }
+                }
+            }
+
+            self.events.push(
+                self.tick,
+                EventKind::HouseholdBankruptcy { household: id, liquidated, written_off, resolution: ins.resolution },
+            );
+        }
+        if ins.resolution == InsolvencyResolution::Remove && !bankrupt.is_empty() {
+            self.households.retain(|idx| bankrupt.binary_search(&idx).is_err());
+        }
     }
 
-    pub fn force_sell_all_stocks(&mut self) {
-        if self.structure.stocks.is_empty() {
+    /// Delists firms whose earnings stayed negative for `loss_intervals` payouts;
+    /// holders and short sellers settle at the recovery price.
+    fn resolve_firm_failures(&mut self) {
+        let Some(fi) = self.structure.firm_insolvency.clone() else {
             return;
+        };
+
+        for i in 0..self.structure.stocks.len() {
+            let sm = &mut self.stock_market;
+            if sm.bankrupt[i] || sm.loss_streak[i] < fi.loss_intervals {
+                continue;
+            }
+            let sid = StockId(i as u32);
+            let recovery = sm.price[i] * fi.recovery_rate;
+            for mut h in self.households.iter_mut() {
+                let q = h.shares(sid);
+                if q != 0.0 {
+                    *h.cash += q * recovery;
+                    self.cash_flows.securities += q * recovery;
+                    h.add_shares(sid, -q);
+                }
+            }
+            sm.bankrupt[i] = true;
+            sm.listed[i] = false;
+            sm.held[i] = 0.0;
+            sm.short_interest[i] = 0.0;
+            sm.earnings[i] = 0.0;
+            sm.dividend_due[i] = 0.0;
+            sm.price[i] = recovery.max(0.01);
+
+            self.events.push(self.tick, EventKind::FirmBankruptcy { stock: sid, recovery_per_share: recovery });
+        }
+    }
+
+    fn apply_corporate_actions(&mut self) {
+        for si in 0..self.structure.stocks.len() {
+            let sid = StockId(si as u32);
+            for ai in 0..self.structure.stocks[si].corporate_actions.len() {
+                let a = &self.structure.stocks[si].corporate_actions[ai];
+                if a.tick != self.tick {
+                    continue;
+                }
+                let (kind, shares, ratio) = (a.kind, a.shares, a.ratio);
+                match kind {
+                    CorporateActionKind::Ipo => {
+                        self.stock_market.listed[si] = true;
+                        self.stock_market.outstanding[si] += shares;
+                    }
+                    CorporateActionKind::SecondaryOffering => {
+                        self.stock_market.outstanding[si] += shares;
+                    }
+                    CorporateActionKind::Buyback => self.buy_back(sid, shares),
+                    CorporateActionKind::Split => self.split(sid, ratio),
+                }
+            }
         }
+    }
+
+    /// Repurchases shares into treasury at the current price: pro rata from
+    /// the households holding them, who are paid, and then from the unheld float.
+    fn buy_back(&mut self, sid: StockId, shares: f64) {
+        let i = sid.0 as usize;
+        let from_holders = shares.min(self.stock_market.held[i]).max(0.0);
+        let p = self.stock_market.price[i];
 
-        for h in &mut self.households {
-            for i in 0..self.structure.stocks.len() {
-                let sid = StockId(i as u32);
-                let qty = h.portfolio.get(sid);
-                if qty <= 0.0 {
+        if from_holders > 0.0 {
+            let frac = from_holders / self.stock_market.held[i];
+            for mut h in self.households.iter_mut() {
+                let take = h.shares(sid) * frac;
+                if take <= 0.0 {
                     continue;
                 }
-                let p = self.stock_market.price[i];
-                let proceeds = qty * p;
-                h.cash += proceeds;
-                h.portfolio.add(sid, -qty);
-                self.stock_market.note_sell(sid, qty);
+                h.add_shares(sid, -take);
+                *h.cash += take * p;
+                self.cash_flows.buybacks += take * p;
             }
+            self.stock_market.held[i] -= from_holders;
+        }
+
+        let from_float = (shares - from_holders).min(self.stock_market.available(sid));
+        self.stock_market.treasury[i] += from_float + from_holders;
+        self.stock_market.demand[i] += from_float + from_holders;
+    }
+
+    /// Multiplies every share count by `ratio`; fractions below the lot size are paid out in cash.
+    fn split(&mut self, sid: StockId, ratio: f64) {
+        let i = sid.0 as usize;
+        let sm = &mut self.stock_market;
+        sm.outstanding[i] *= ratio;
+        sm.treasury[i] *= ratio;
+        sm.price[i] = (sm.price[i] / ratio).max(0.01);
+        sm.earnings[i] /= ratio;
+        sm.last_dividend[i] /= ratio;
+        self.indices.on_split(sid, ratio);
+
+        let p = sm.price[i];
+        let mut held = 0.0;
+        let mut short = 0.0;
+        for mut h in self.households.iter_mut() {
+            let q = h.shares(sid);
+            if q == 0.0 {
+                continue;
+            }
+            let scaled = q * ratio;
+            let kept = sm.round_lot(&self.structure, sid, scaled);
+            h.add_shares(sid, kept - q);
+            *h.cash += (scaled - kept) * p;
+            self.cash_flows.securities += (scaled - kept) * p;
+            held += kept.max(0.0);
+            short += (-kept).max(0.0);
+        }
+        sm.held[i] = held;
+        sm.short_interest[i] = short;
+    }
+
+    pub fn force_sell_all_stocks(&mut self) {
+        if self.structure.stocks.is_empty() {
+            return;
+        }
+
+        // Longs are sold and shorts bought back, leaving no exposure.
+        for mut h in self.households.iter_mut() {
+            close_positions(&mut h, &mut self.stock_market, &mut self.cash_flows);
+        }
+    }
+}
+
+/// Random stream for one household on one tick, derived from the master seed so
+/// draws don't depend on the order households are visited in.
+fn household_rng(seed: u64, tick: u64, id: HouseholdId) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed ^ tick.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    rng.set_stream(id.0 as u64);
+    rng
+}
+
+/// Moves a household's position by `delta` shares at the current price, netting
+/// against any existing position before opening a new long or short.
+fn execute_trade(h: &mut HouseholdMut, sm: &mut StockMarket, flows: &mut CashFlows, sid: StockId, delta: f64) {
+    let q = h.shares(sid);
+    if delta > 0.0 {
+        let cover = delta.min((-q).max(0.0));
+        sm.note_cover(sid, cover);
+        sm.note_buy(sid, delta - cover);
+    } else {
+        let sell = (-delta).min(q.max(0.0));
+        sm.note_sell(sid, sell);
+        sm.note_short(sid, -delta - sell);
+    }
+    h.add_shares(sid, delta);
+    *h.cash -= delta * sm.price[sid.0 as usize];
+    flows.securities -= delta * sm.price[sid.0 as usize];
+}
+
+/// Buys `qty` shares, borrowing the part of the cost above `1 / leverage` as
+/// long as gross exposure stays within `leverage` times equity.
+fn buy_long(h: &mut HouseholdMut, sm: &mut StockMarket, flows: &mut CashFlows, sid: StockId, qty: f64, leverage: f64) {
+    if qty <= 0.0 {
+        return;
+    }
+    let p = sm.price[sid.0 as usize];
+    let headroom = h.equity(&sm.price) * leverage - h.gross_exposure(&sm.price);
+    let cost = qty * p;
+    let borrowed = cost * (1.0 - 1.0 / leverage);
+    if cost > headroom || *h.cash < cost - borrowed {
+        return;
+    }
+    *h.cash += borrowed;
+    *h.margin_debt += borrowed;
+    flows.margin += borrowed;
+    execute_trade(h, sm, flows, sid, qty);
+}
+
+/// Liquidates every position and repays as much margin debt as the proceeds allow.
+fn close_positions(h: &mut HouseholdMut, sm: &mut StockMarket, flows: &mut CashFlows) {
+    for i in 0..h.portfolio.len() {
+        let q = h.portfolio[i];
+        if q != 0.0 {
+            execute_trade(h, sm, flows, StockId(i as u32), -q);
         }
     }
+    flows.margin -= h.repay_margin();
 }
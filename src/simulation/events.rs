@@ -0,0 +1,83 @@
+use crate::data::compiled::{InsolvencyResolution, Structure};
+use crate::data::ids::{ShockId, StockId};
+use crate::simulation::household::HouseholdId;
+use std::collections::VecDeque;
+
+pub const EVENT_LOG_LEN: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum EventKind {
+    MarginCall {
+        household: HouseholdId,
+    },
+    HouseholdBankruptcy {
+        household: HouseholdId,
+        liquidated: f64,
+        written_off: f64,
+        resolution: InsolvencyResolution,
+    },
+    FirmBankruptcy {
+        stock: StockId,
+        recovery_per_share: f64,
+    },
+    SupplyShock {
+        shock: ShockId,
+        until: u64,
+    },
+}
+
+impl EventKind {
+    pub fn household(&self) -> Option<HouseholdId> {
+        match self {
+            EventKind::MarginCall { household } | EventKind::HouseholdBankruptcy { household, .. } => Some(*household),
+            EventKind::FirmBankruptcy { .. } | EventKind::SupplyShock { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimEvent {
+    pub tick: u64,
+    pub kind: EventKind,
+}
+
+impl SimEvent {
+    pub fn describe(&self, s: &Structure) -> String {
+        match &self.kind {
+            EventKind::MarginCall { household } => format!("margin call on household #{}", household.0),
+            EventKind::HouseholdBankruptcy { household, liquidated, written_off, resolution } => format!(
+                "household #{} bankrupt: liquidated {:.2}, wrote off {:.2}, {:?}",
+                household.0, liquidated, written_off, resolution
+            ),
+            EventKind::FirmBankruptcy { stock, recovery_per_share } => {
+                format!("{} bankrupt: delisted, {:.2} recovered per share", s.stock_name(*stock), recovery_per_share)
+            }
+            EventKind::SupplyShock { shock, until } => {
+                let sh = &s.shocks[shock.0 as usize];
+                format!("{}: output at {:.0}% until {}", sh.display_name, sh.multiplier * 100.0, s.calendar.date(*until))
+            }
+        }
+    }
+}
+
+/// Keeps the most recent events plus running totals that survive eviction.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    pub events: VecDeque<SimEvent>,
+    pub household_bankruptcies: u64,
+    pub firm_bankruptcies: u64,
+}
+
+impl EventLog {
+    pub fn push(&mut self, tick: u64, kind: EventKind) {
+        match kind {
+            EventKind::HouseholdBankruptcy { .. } => self.household_bankruptcies += 1,
+            EventKind::FirmBankruptcy { .. } => self.firm_bankruptcies += 1,
+            EventKind::MarginCall { .. } | EventKind::SupplyShock { .. } => {}
+        }
+        if self.events.len() == EVENT_LOG_LEN {
+            self.events.pop_front();
+        }
+        self.events.push_back(SimEvent { tick, kind });
+    }
+}
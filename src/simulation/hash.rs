@@ -0,0 +1,151 @@
+use crate::data::compiled::Structure;
+use crate::simulation::events::EventLog;
+use crate::simulation::household::Households;
+use crate::simulation::indices::StockIndices;
+use crate::simulation::market::Market;
+use crate::simulation::production::Production;
+use crate::simulation::stock_market::StockMarket;
+
+/// FNV-1a over little-endian bytes. Unlike `DefaultHasher` its output is fixed
+/// across platforms and compiler versions, so hashes can be written to disk.
+#[derive(Debug, Clone)]
+pub struct StateHasher(u64);
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StateHasher {
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub fn write_u64(&mut self, v: u64) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_u128(&mut self, v: u128) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_f64(&mut self, v: f64) {
+        self.write_u64(v.to_bits());
+    }
+
+    pub fn write_bool(&mut self, v: bool) {
+        self.write_bytes(&[v as u8]);
+    }
+
+    pub fn write_f64s(&mut self, vs: &[f64]) {
+        self.write_u64(vs.len() as u64);
+        for &v in vs {
+            self.write_f64(v);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub trait StateHash {
+    fn hash_state(&self, h: &mut StateHasher);
+}
+
+/// Hashes households row by row, each need with its id in declaration order.
+/// Only the archetype's own need slots are written, not the padding up to the
+/// widest archetype, so the hash does not depend on how the columns are laid out.
+pub fn hash_households(hh: &Households, s: &Structure, h: &mut StateHasher) {
+    h.write_u64(hh.len() as u64);
+    for row in hh.iter() {
+        h.write_u64(row.id.0 as u64);
+        h.write_u64(row.kind.0 as u64);
+        h.write_f64(row.cash);
+        h.write_f64(row.utility);
+        h.write_f64(row.margin_debt);
+        h.write_u64(row.distress_ticks);
+        h.write_f64s(row.inventory);
+        h.write_f64s(row.portfolio);
+        let td = &s.household_types[row.kind.0 as usize];
+        h.write_u64(td.needs.len() as u64);
+        for &slot in &td.declared_needs {
+            h.write_u64(td.needs[slot].0 as u64);
+            h.write_u64(row.next_due_in[slot]);
+            h.write_bool(row.fulfilled_last[slot]);
+        }
+        h.write_u64(row.last_consumed.len() as u64);
+        for &(g, q) in row.last_consumed {
+            h.write_u64(g.0 as u64);
+            h.write_f64(q);
+        }
+    }
+}
+
+impl StateHash for Market {
+    fn hash_state(&self, h: &mut StateHasher) {
+        h.write_f64s(&self.price);
+        h.write_f64s(&self.demand);
+        h.write_f64s(&self.supply);
+        h.write_f64(self.adjustment_rate);
+    }
+}
+
+impl StateHash for StockMarket {
+    fn hash_state(&self, h: &mut StateHasher) {
+        h.write_f64s(&self.price);
+        h.write_f64s(&self.demand);
+        h.write_f64s(&self.supply);
+        h.write_f64s(&self.earnings);
+        h.write_f64s(&self.dividend_due);
+        h.write_f64s(&self.last_dividend);
+        h.write_f64s(&self.outstanding);
+        h.write_f64s(&self.held);
+        h.write_f64s(&self.treasury);
+        h.write_f64s(&self.short_interest);
+        h.write_f64(self.discount_rate);
+        h.write_f64(self.value_pull);
+        for i in 0..self.price.len() {
+            h.write_bool(self.listed[i]);
+            h.write_bool(self.bankrupt[i]);
+            h.write_u64(self.loss_streak[i] as u64);
+        }
+    }
+}
+
+impl StateHash for StockIndices {
+    fn hash_state(&self, h: &mut StateHasher) {
+        h.write_f64s(&self.value);
+        h.write_f64s(&self.prev_value);
+        h.write_f64s(&self.prev_price);
+    }
+}
+
+impl StateHash for Production {
+    fn hash_state(&self, h: &mut StateHasher) {
+        h.write_u64(self.shock_until.len() as u64);
+        for until in &self.shock_until {
+            h.write_u64(until.map_or(u64::MAX, |t| t));
+        }
+        h.write_f64s(&self.last_multiplier);
+        h.write_f64s(&self.stock);
+        for held in &self.capital {
+            h.write_f64s(held);
+        }
+    }
+}
+
+impl StateHash for EventLog {
+    fn hash_state(&self, h: &mut StateHasher) {
+        h.write_u64(self.events.len() as u64);
+        h.write_u64(self.household_bankruptcies);
+        h.write_u64(self.firm_bankruptcies);
+        for ev in &self.events {
+            h.write_u64(ev.tick);
+        }
+    }
+}
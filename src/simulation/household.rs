@@ -1,145 +1,637 @@
 // src/simulation/household.rs
-use crate::data::compiled::{NeedDef, Structure};
-use crate::data::ids::{GoodId, HouseholdTypeId, NeedId, StockId};
+use crate::data::compiled::{Overflow, Structure};
+use crate::data::ids::{GoodId, HouseholdTypeId, StockId};
+use crate::simulation::migrate::IdMap;
 use smallvec::SmallVec;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct HouseholdId(pub u32);
 
-#[derive(Debug, Clone)]
-pub struct Inventory {
-    pub qty: Vec<f64>,
+pub type Consumed = SmallVec<[(GoodId, f64); 8]>;
+
+/// Households stored column by column. Inventory and portfolio are row-major
+/// `len × goods` and `len × stocks` matrices; need timers use a fixed number of
+/// slots per household, slot `i` belonging to `needs[i]` of its archetype.
+#[derive(Debug, Clone, Default)]
+pub struct Households {
+    pub id: Vec<HouseholdId>,
+    pub kind: Vec<HouseholdTypeId>,
+    pub cash: Vec<f64>,
+    pub utility: Vec<f64>,
+    pub margin_debt: Vec<f64>,
+    pub distress_ticks: Vec<u64>,
+    pub inventory: Vec<f64>,
+    pub portfolio: Vec<f64>,
+    pub next_due_in: Vec<u64>,
+    pub fulfilled_last: Vec<bool>,
+    pub last_consumed: Vec<Consumed>,
+    goods: usize,
+    stocks: usize,
+    need_slots: usize,
 }
 
-impl Inventory {
-    pub fn new(goods_len: usize) -> Self {
-        Self { qty: vec![0.0; goods_len] }
+impl Households {
+    pub fn new(s: &Structure) -> Self {
+        Self {
+            goods: s.goods.len(),
+            stocks: s.stocks.len(),
+            need_slots: s.household_types.iter().map(|t| t.needs.len()).max().unwrap_or(0),
+            ..Self::default()
+        }
     }
 
-    pub fn get(&self, g: GoodId) -> f64 {
-        self.qty[g.0 as usize]
+    pub fn len(&self) -> usize {
+        self.id.len()
     }
 
-    pub fn add(&mut self, g: GoodId, amount: f64) {
-        let v = &mut self.qty[g.0 as usize];
-        *v += amount;
-        if *v < 0.0 {
-            *v = 0.0;
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty()
+    }
+
+    /// Appends a household in its archetype's starting state; `portfolio` holds
+    /// the shares actually allocated to it.
+    pub fn push(&mut self, s: &Structure, id: HouseholdId, kind: HouseholdTypeId, portfolio: &[(StockId, f64)]) {
+        self.id.push(id);
+        self.kind.push(kind);
+        self.cash.push(0.0);
+        self.utility.push(0.0);
+        self.margin_debt.push(0.0);
+        self.distress_ticks.push(0);
+        self.inventory.resize(self.inventory.len() + self.goods, 0.0);
+        self.portfolio.resize(self.portfolio.len() + self.stocks, 0.0);
+        self.next_due_in.resize(self.next_due_in.len() + self.need_slots, 0);
+        self.fulfilled_last.resize(self.fulfilled_last.len() + self.need_slots, true);
+        self.last_consumed.push(Consumed::new());
+        self.reset(self.len() - 1, s, id, kind, portfolio);
+    }
+
+    /// Overwrites row `idx` with a fresh household of archetype `kind`.
+    pub fn reset(&mut self, idx: usize, s: &Structure, id: HouseholdId, kind: HouseholdTypeId, portfolio: &[(StockId, f64)]) {
+        let td = &s.household_types[kind.0 as usize];
+        self.id[idx] = id;
+        self.kind[idx] = kind;
+        let mut h = self.get_mut(idx);
+        *h.cash = td.starting_cash;
+        *h.utility = 0.0;
+        *h.margin_debt = 0.0;
+        *h.distress_ticks = 0;
+        h.inventory.fill(0.0);
+        for &(g, q) in &td.starting_inventory {
+            h.store(s, g, q);
+        }
+        h.portfolio.fill(0.0);
+        for &(sid, q) in portfolio {
+            h.add_shares(sid, q);
+        }
+        h.next_due_in.fill(0);
+        h.fulfilled_last.fill(true);
+        for (slot, &nid) in td.needs.iter().enumerate() {
+            h.next_due_in[slot] = s.needs[nid.0 as usize].interval_ticks;
+        }
+        h.last_consumed.clear();
+    }
+
+    /// Row of household `id`. Rows stay in id order: households are only ever
+    /// appended with increasing ids and removals keep the order.
+    pub fn position(&self, id: HouseholdId) -> Option<usize> {
+        self.id.binary_search_by_key(&id.0, |h| h.0).ok()
+    }
+
+    /// Drops the rows for which `keep` is false, compacting every column in a
+    /// single pass; the remaining rows keep their order.
+    pub fn retain(&mut self, mut keep: impl FnMut(usize) -> bool) {
+        let (g, s, n) = (self.goods, self.stocks, self.need_slots);
+        let mut kept = 0;
+        for idx in 0..self.len() {
+            if !keep(idx) {
+                continue;
+            }
+            if kept != idx {
+                self.id[kept] = self.id[idx];
+                self.kind[kept] = self.kind[idx];
+                self.cash[kept] = self.cash[idx];
+                self.utility[kept] = self.utility[idx];
+                self.margin_debt[kept] = self.margin_debt[idx];
+                self.distress_ticks[kept] = self.distress_ticks[idx];
+                self.inventory.copy_within(idx * g..(idx + 1) * g, kept * g);
+                self.portfolio.copy_within(idx * s..(idx + 1) * s, kept * s);
+                self.next_due_in.copy_within(idx * n..(idx + 1) * n, kept * n);
+                self.fulfilled_last.copy_within(idx * n..(idx + 1) * n, kept * n);
+                self.last_consumed.swap(kept, idx);
+            }
+            kept += 1;
+        }
+        self.id.truncate(kept);
+        self.kind.truncate(kept);
+        self.cash.truncate(kept);
+        self.utility.truncate(kept);
+        self.margin_debt.truncate(kept);
+        self.distress_ticks.truncate(kept);
+        self.inventory.truncate(kept * g);
+        self.portfolio.truncate(kept * s);
+        self.next_due_in.truncate(kept * n);
+        self.fulfilled_last.truncate(kept * n);
+        self.last_consumed.truncate(kept);
+    }
+
+    /// Rebuilds the columns for structure `s`. `slots[kind][i]` is the old slot
+    /// of the new archetype's `i`-th need; every household's archetype must
+    /// survive in `kinds`.
+    pub fn migrate(&self, s: &Structure, goods: &IdMap, stocks: &IdMap, kinds: &IdMap, slots: &[Vec<Option<usize>>]) -> (Households, f64) {
+        let mut out = Households::new(s);
+        let mut over = 0.0;
+        for h in self.iter() {
+            let kind = HouseholdTypeId(kinds.new_index(h.kind.0 as usize).expect("archetype removed while in use") as u32);
+            out.id.push(h.id);
+            out.kind.push(kind);
+            out.cash.push(h.cash);
+            out.utility.push(h.utility);
+            out.margin_debt.push(h.margin_debt);
+            out.distress_ticks.push(h.distress_ticks);
+            out.inventory.resize(out.inventory.len() + out.goods, 0.0);
+            out.portfolio.extend((0..out.stocks).map(|i| stocks.old_index(i).map_or(0.0, |o| h.portfolio[o])));
+            let td = &s.household_types[kind.0 as usize];
+            for slot in 0..out.need_slots {
+                let (due, fulfilled) = match (td.needs.get(slot), slots[kind.0 as usize].get(slot).copied().flatten()) {
+                    (Some(n), Some(o)) => (h.next_due_in[o].min(s.needs[n.0 as usize].interval_ticks), h.fulfilled_last[o]),
+                    (Some(n), None) => (s.needs[n.0 as usize].interval_ticks, true),
+                    (None, _) => (0, true),
+                };
+                out.next_due_in.push(due);
+                out.fulfilled_last.push(fulfilled);
+            }
+            out.last_consumed
+                .push(h.last_consumed.iter().filter_map(|&(g, q)| goods.new_index(g.0 as usize).map(|n| (GoodId(n as u32), q))).collect());
+            // Holdings are stored again, as volumes or capacity may have shrunk.
+            let mut row = out.get_mut(out.len() - 1);
+            for g in 0..s.goods.len() {
+                if let Some(o) = goods.old_index(g) {
+                    over += row.store(s, GoodId(g as u32), h.inventory[o]);
+                }
+            }
+        }
+        (out, over)
+    }
+
+    pub fn get(&self, idx: usize) -> HouseholdRef<'_> {
+        let (g, s, n) = (self.goods, self.stocks, self.need_slots);
+        HouseholdRef {
+            id: self.id[idx],
+            kind: self.kind[idx],
+            cash: self.cash[idx],
+            utility: self.utility[idx],
+            margin_debt: self.margin_debt[idx],
+            distress_ticks: self.distress_ticks[idx],
+            inventory: &self.inventory[idx * g..(idx + 1) * g],
+            portfolio: &self.portfolio[idx * s..(idx + 1) * s],
+            next_due_in: &self.next_due_in[idx * n..(idx + 1) * n],
+            fulfilled_last: &self.fulfilled_last[idx * n..(idx + 1) * n],
+            last_consumed: &self.last_consumed[idx],
+        }
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> HouseholdMut<'_> {
+        let (g, s, n) = (self.goods, self.stocks, self.need_slots);
+        HouseholdMut {
+            id: self.id[idx],
+            kind: self.kind[idx],
+            cash: &mut self.cash[idx],
+            utility: &mut self.utility[idx],
+            margin_debt: &mut self.margin_debt[idx],
+            distress_ticks: &mut self.distress_ticks[idx],
+            inventory: &mut self.inventory[idx * g..(idx + 1) * g],
+            portfolio: &mut self.portfolio[idx * s..(idx + 1) * s],
+            next_due_in: &mut self.next_due_in[idx * n..(idx + 1) * n],
+            fulfilled_last: &mut self.fulfilled_last[idx * n..(idx + 1) * n],
+            last_consumed: &mut self.last_consumed[idx],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = HouseholdRef<'_>> {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    /// Mutable view over every household; iterate it or split it into chunks for parallel work.
+    pub fn iter_mut(&mut self) -> HouseholdsMut<'_> {
+        HouseholdsMut {
+            id: &self.id,
+            kind: &self.kind,
+            cash: &mut self.cash,
+            utility: &mut self.utility,
+            margin_debt: &mut self.margin_debt,
+            distress_ticks: &mut self.distress_ticks,
+            inventory: &mut self.inventory,
+            portfolio: &mut self.portfolio,
+            next_due_in: &mut self.next_due_in,
+            fulfilled_last: &mut self.fulfilled_last,
+            last_consumed: &mut self.last_consumed,
+            goods: self.goods,
+            stocks: self.stocks,
+            need_slots: self.need_slots,
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Portfolio {
-    pub qty: Vec<f64>,
+/// A contiguous run of households borrowed column by column.
+#[derive(Debug)]
+pub struct HouseholdsMut<'a> {
+    id: &'a [HouseholdId],
+    kind: &'a [HouseholdTypeId],
+    cash: &'a mut [f64],
+    utility: &'a mut [f64],
+    margin_debt: &'a mut [f64],
+    distress_ticks: &'a mut [u64],
+    inventory: &'a mut [f64],
+    portfolio: &'a mut [f64],
+    next_due_in: &'a mut [u64],
+    fulfilled_last: &'a mut [bool],
+    last_consumed: &'a mut [Consumed],
+    goods: usize,
+    stocks: usize,
+    need_slots: usize,
 }
 
-impl Portfolio {
-    pub fn new(stocks_len: usize) -> Self {
-        Self { qty: vec![0.0; stocks_len] }
+impl<'a> HouseholdsMut<'a> {
+    pub fn len(&self) -> usize {
+        self.id.len()
     }
 
-    pub fn get(&self, s: StockId) -> f64 {
-        self.qty[s.0 as usize]
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty()
     }
 
-    pub fn add(&mut self, s: StockId, amount: f64) {
-        let v = &mut self.qty[s.0 as usize];
-        *v += amount;
-        if *v < 0.0 {
-            *v = 0.0;
+    pub fn split_at(self, mid: usize) -> (Self, Self) {
+        let Self {
+            id,
+            kind,
+            cash,
+            utility,
+            margin_debt,
+            distress_ticks,
+            inventory,
+            portfolio,
+            next_due_in,
+            fulfilled_last,
+            last_consumed,
+            goods,
+            stocks,
+            need_slots,
+        } = self;
+        let (id0, id1) = id.split_at(mid);
+        let (kind0, kind1) = kind.split_at(mid);
+        let (cash0, cash1) = cash.split_at_mut(mid);
+        let (utility0, utility1) = utility.split_at_mut(mid);
+        let (debt0, debt1) = margin_debt.split_at_mut(mid);
+        let (distress0, distress1) = distress_ticks.split_at_mut(mid);
+        let (inv0, inv1) = inventory.split_at_mut(mid * goods);
+        let (port0, port1) = portfolio.split_at_mut(mid * stocks);
+        let (due0, due1) = next_due_in.split_at_mut(mid * need_slots);
+        let (ful0, ful1) = fulfilled_last.split_at_mut(mid * need_slots);
+        let (cons0, cons1) = last_consumed.split_at_mut(mid);
+        let part = |id, kind, cash, utility, margin_debt, distress_ticks, inventory, portfolio, next_due_in, fulfilled_last, last_consumed| Self {
+            id,
+            kind,
+            cash,
+            utility,
+            margin_debt,
+            distress_ticks,
+            inventory,
+            portfolio,
+            next_due_in,
+            fulfilled_last,
+            last_consumed,
+            goods,
+            stocks,
+            need_slots,
+        };
+        (
+            part(id0, kind0, cash0, utility0, debt0, distress0, inv0, port0, due0, ful0, cons0),
+            part(id1, kind1, cash1, utility1, debt1, distress1, inv1, port1, due1, ful1, cons1),
+        )
+    }
+
+    /// Splits into runs of at most `size` households, in order.
+    pub fn split_chunks(mut self, size: usize) -> Vec<Self> {
+        let mut out = Vec::with_capacity(self.len().div_ceil(size.max(1)));
+        while self.len() > size {
+            let (head, tail) = self.split_at(size);
+            out.push(head);
+            self = tail;
         }
+        if !self.is_empty() {
+            out.push(self);
+        }
+        out
+    }
+}
+
+impl<'a> Iterator for HouseholdsMut<'a> {
+    type Item = HouseholdMut<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&id, ids) = self.id.split_first()?;
+        let (&kind, kinds) = self.kind.split_first()?;
+        self.id = ids;
+        self.kind = kinds;
+        Some(HouseholdMut {
+            id,
+            kind,
+            cash: take_first(&mut self.cash),
+            utility: take_first(&mut self.utility),
+            margin_debt: take_first(&mut self.margin_debt),
+            distress_ticks: take_first(&mut self.distress_ticks),
+            inventory: take_row(&mut self.inventory, self.goods),
+            portfolio: take_row(&mut self.portfolio, self.stocks),
+            next_due_in: take_row(&mut self.next_due_in, self.need_slots),
+            fulfilled_last: take_row(&mut self.fulfilled_last, self.need_slots),
+            last_consumed: take_first(&mut self.last_consumed),
+        })
     }
 
-    pub fn clear_all(&mut self) {
-        self.qty.fill(0.0);
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct NeedState {
-    pub need: NeedId,
-    pub next_due_in: u64,
-    pub fulfilled_last: bool,
+fn take_first<'a, T>(column: &mut &'a mut [T]) -> &'a mut T {
+    let (first, rest) = std::mem::take(column).split_first_mut().expect("column shorter than id column");
+    *column = rest;
+    first
+}
+
+fn take_row<'a, T>(matrix: &mut &'a mut [T], width: usize) -> &'a mut [T] {
+    let (row, rest) = std::mem::take(matrix).split_at_mut(width);
+    *matrix = rest;
+    row
 }
 
-#[derive(Debug, Clone)]
-pub struct Household {
+#[derive(Debug, Clone, Copy)]
+pub struct HouseholdRef<'a> {
     pub id: HouseholdId,
     pub kind: HouseholdTypeId,
     pub cash: f64,
-    pub inventory: Inventory,
-    pub portfolio: Portfolio,
-    pub needs: Vec<NeedState>,
     pub utility: f64,
-    pub last_consumed: SmallVec<[(GoodId, f64); 8]>,
+    pub margin_debt: f64,
+    pub distress_ticks: u64,
+    pub inventory: &'a [f64],
+    pub portfolio: &'a [f64],
+    pub next_due_in: &'a [u64],
+    pub fulfilled_last: &'a [bool],
+    pub last_consumed: &'a Consumed,
 }
 
-impl Household {
-    pub fn new(
-        id: HouseholdId,
-        kind: HouseholdTypeId,
-        cash: f64,
-        inventory: Inventory,
-        portfolio: Portfolio,
-        needs: Vec<NeedState>,
-    ) -> Self {
-        Self {
-            id,
-            kind,
-            cash,
-            inventory,
-            portfolio,
-            needs,
-            utility: 0.0,
-            last_consumed: SmallVec::new(),
+impl HouseholdRef<'_> {
+    pub fn good(&self, g: GoodId) -> f64 {
+        self.inventory[g.0 as usize]
+    }
+
+    pub fn volume_used(&self, s: &Structure) -> f64 {
+        volume_used(self.inventory, s)
+    }
+
+    /// Negative holdings are short positions.
+    pub fn shares(&self, s: StockId) -> f64 {
+        self.portfolio[s.0 as usize]
+    }
+
+    pub fn equity(&self, stock_prices: &[f64]) -> f64 {
+        self.cash + market_value(self.portfolio, stock_prices) - self.margin_debt
+    }
+
+    /// Equity plus the inventory at market prices.
+    pub fn net_worth(&self, goods_prices: &[f64], stock_prices: &[f64]) -> f64 {
+        self.equity(stock_prices) + market_value(self.inventory, goods_prices)
+    }
+}
+
+#[derive(Debug)]
+pub struct HouseholdMut<'a> {
+    pub id: HouseholdId,
+    pub kind: HouseholdTypeId,
+    pub cash: &'a mut f64,
+    pub utility: &'a mut f64,
+    pub margin_debt: &'a mut f64,
+    pub distress_ticks: &'a mut u64,
+    pub inventory: &'a mut [f64],
+    pub portfolio: &'a mut [f64],
+    pub next_due_in: &'a mut [u64],
+    pub fulfilled_last: &'a mut [bool],
+    pub last_consumed: &'a mut Consumed,
+}
+
+impl HouseholdMut<'_> {
+    pub fn good(&self, g: GoodId) -> f64 {
+        self.inventory[g.0 as usize]
+    }
+
+    /// Takes up to `amount` of `g` out of the inventory.
+    pub fn remove_good(&mut self, g: GoodId, amount: f64) {
+        self.add_good(g, -amount);
+    }
+
+    /// Inventories never go below zero. Goods only come in through
+    /// [`store`](Self::store), which keeps them within storage capacity.
+    fn add_good(&mut self, g: GoodId, amount: f64) {
+        let v = &mut self.inventory[g.0 as usize];
+        *v += amount;
+        if *v < 0.0 {
+            *v = 0.0;
+        }
+    }
+
+    /// Units of `g` held. Non-stackable goods come in whole units, and a worn
+    /// one counts until half of it is gone.
+    pub fn units(&self, s: &Structure, g: GoodId) -> f64 {
+        let q = self.good(g);
+        if s.goods[g.0 as usize].stackable {
+            q
+        } else {
+            (q + 0.5 - 1e-9).floor()
         }
     }
 
+    /// How much of `g` is of use: worn units in proportion to what is left of
+    /// them, and nothing beyond the units held.
+    pub fn usable(&self, s: &Structure, g: GoodId) -> f64 {
+        self.good(g).min(self.units(s, g))
+    }
+
+    pub fn volume_used(&self, s: &Structure) -> f64 {
+        volume_used(self.inventory, s)
+    }
+
+    /// Adds `amount` of `g` within the archetype's storage capacity and returns
+    /// the part that did not fit: not added under [`Overflow::Refuse`], added
+    /// and removed again otherwise. Non-stackable goods overflow in whole units.
+    /// Every addition to an inventory goes through here: purchases, starting
+    /// inventories and holdings carried over by a migration.
+    pub fn store(&mut self, s: &Structure, g: GoodId, amount: f64) -> f64 {
+        let gd = &s.goods[g.0 as usize];
+        let storage = s.household_types[self.kind.0 as usize].storage;
+        let Some(st) = storage.filter(|_| gd.volume > 0.0 && amount > 0.0) else {
+            self.add_good(g, amount);
+            return 0.0;
+        };
+        let whole = |q: f64| if gd.stackable { q } else { q.ceil() };
+        let excess = self.volume_used(s) + amount * gd.volume - st.capacity;
+        if excess <= 0.0 {
+            self.add_good(g, amount);
+            return 0.0;
+        }
+        let over = whole(excess / gd.volume).min(amount);
+        match st.overflow {
+            Overflow::Refuse => self.add_good(g, amount - over),
+            Overflow::Discard | Overflow::Sell => self.add_good(g, amount),
+        }
+        if st.overflow != Overflow::Refuse {
+            self.remove_good(g, over);
+        }
+        over
+    }
+
+    /// Charges the archetype's storage cost for the volume held and returns it.
+    pub fn pay_storage(&mut self, s: &Structure) -> f64 {
+        let Some(st) = s.household_types[self.kind.0 as usize].storage else { return 0.0 };
+        if st.cost_per_volume <= 0.0 {
+            return 0.0;
+        }
+        let cost = self.volume_used(s) * st.cost_per_volume;
+        *self.cash -= cost;
+        cost
+    }
+
+    pub fn shares(&self, s: StockId) -> f64 {
+        self.portfolio[s.0 as usize]
+    }
+
+    /// Negative holdings are short positions.
+    pub fn add_shares(&mut self, s: StockId, amount: f64) {
+        self.portfolio[s.0 as usize] += amount;
+    }
+
+    pub fn gross_exposure(&self, prices: &[f64]) -> f64 {
+        self.portfolio.iter().zip(prices).map(|(q, p)| q.abs() * p).sum()
+    }
+
+    pub fn has_short(&self) -> bool {
+        self.portfolio.iter().any(|&q| q < 0.0)
+    }
+
+    pub fn equity(&self, stock_prices: &[f64]) -> f64 {
+        *self.cash + market_value(self.portfolio, stock_prices) - *self.margin_debt
+    }
+
+    /// Repays as much margin debt as cash allows and returns the amount repaid.
+    pub fn repay_margin(&mut self) -> f64 {
+        let repay = self.cash.max(0.0).min(*self.margin_debt);
+        *self.cash -= repay;
+        *self.margin_debt -= repay;
+        repay
+    }
+
     pub fn apply_income(&mut self, income_per_tick: f64) {
-        self.cash += income_per_tick;
+        *self.cash += income_per_tick;
     }
 
+    /// Held durables slow the decay of the goods they service, in proportion to
+    /// how much of the durable is usable (up to one unit). Non-stackable goods
+    /// wear until a unit is half gone and is thrown out.
     pub fn apply_decay(&mut self, s: &Structure) {
         for gd in &s.goods {
-            let q = self.inventory.get(gd.id);
+            let q = self.good(gd.id);
             if q <= 0.0 || gd.decay_rate <= 0.0 {
                 continue;
             }
-            let decayed = q * gd.decay_rate;
-            self.inventory.add(gd.id, -decayed);
+            let mut rate = gd.decay_rate;
+            for &(d, m) in &gd.serviced_by {
+                rate *= 1.0 - (1.0 - m) * self.usable(s, d).min(1.0);
+            }
+            let decayed = q * rate;
+            self.remove_good(gd.id, decayed);
+            if !gd.stackable {
+                let scrap = self.good(gd.id) - self.usable(s, gd.id);
+                self.remove_good(gd.id, scrap);
+            }
         }
     }
 
     pub fn step_needs(&mut self, s: &Structure) {
         self.last_consumed.clear();
-        let mut indices: Vec<usize> = (0..self.needs.len()).collect();
-        indices.sort_by_key(|&i| {
-            let nd = &s.needs[self.needs[i].need.0 as usize];
-            (nd.priority, self.needs[i].need.0)
-        });
-
-        for i in indices {
-            let state = &mut self.needs[i];
-            if state.next_due_in > 0 {
-                state.next_due_in -= 1;
+        for (slot, &nid) in s.household_types[self.kind.0 as usize].needs.iter().enumerate() {
+            if self.next_due_in[slot] > 0 {
+                self.next_due_in[slot] -= 1;
                 continue;
             }
 
-            let nd: &NeedDef = &s.needs[state.need.0 as usize];
-            let have = self.inventory.get(nd.good);
-
-            if have >= nd.amount {
-                self.inventory.add(nd.good, -nd.amount);
-                self.utility += 1.0;
-                state.fulfilled_last = true;
+            let nd = &s.needs[nid.0 as usize];
+            let gd = &s.goods[nd.good.0 as usize];
+            let held = self.usable(s, nd.good);
+            if !gd.category.is_consumed() && held > 0.0 {
+                // Durables are used, not used up, and a worn one gives less. Stackable
+                // ones are topped up; whole units are only replaced once thrown out.
+                *self.utility += gd.utility * (held / nd.amount).min(1.0);
+                self.fulfilled_last[slot] = self.units(s, nd.good) >= nd.amount;
+            } else if held >= nd.amount {
+                self.remove_good(nd.good, nd.amount);
+                *self.utility += gd.utility;
+                self.fulfilled_last[slot] = true;
                 self.last_consumed.push((nd.good, nd.amount));
             } else {
-                self.utility -= 0.5;
-                state.fulfilled_last = false;
+                *self.utility -= 0.5;
+                self.fulfilled_last[slot] = false;
+            }
+
+            self.next_due_in[slot] = nd.interval_ticks;
+        }
+    }
+
+    /// Buys the goods for every need that went unmet this tick, recording the
+    /// wanted amounts in `demand` whether or not the household can afford them.
+    /// Worn durables are only topped up to the needed amount and non-stackable
+    /// goods are bought in whole units for the units missing. Goods that do not fit in storage are
+    /// refused, or discarded or sold back (recorded in `supply`) after paying.
+    /// Returns the net cash spent.
+    pub fn buy_unmet_needs(&mut self, s: &Structure, prices: &[f64], demand: &mut [f64], supply: &mut [f64]) -> f64 {
+        let mut want: SmallVec<[(GoodId, f64); 8]> = SmallVec::new();
+        for (slot, &nid) in s.household_types[self.kind.0 as usize].needs.iter().enumerate() {
+            if self.fulfilled_last[slot] {
+                continue;
             }
+            let nd = &s.needs[nid.0 as usize];
+            let gd = &s.goods[nd.good.0 as usize];
+            let amount = if gd.category.is_consumed() { nd.amount } else { (nd.amount - self.units(s, nd.good)).max(0.0) };
+            want.push((nd.good, if gd.stackable { amount } else { amount.ceil() }));
+        }
+        want.sort_by_key(|(g, _)| g.0);
 
-            state.next_due_in = nd.interval_ticks;
+        let overflow = s.household_types[self.kind.0 as usize].storage.map(|st| st.overflow);
+        let mut spent = 0.0;
+        for (g, amount) in want {
+            let price = prices[g.0 as usize];
+            let cost = price * amount;
+            demand[g.0 as usize] += amount.max(0.0);
+            if *self.cash >= cost {
+                let over = self.store(s, g, amount);
+                let paid = match overflow {
+                    Some(Overflow::Refuse) => cost - over * price,
+                    Some(Overflow::Sell) => {
+                        supply[g.0 as usize] += over;
+                        cost - over * price
+                    }
+                    _ => cost,
+                };
+                *self.cash -= paid;
+                spent += paid;
+            }
         }
+        spent
     }
 }
+
+fn volume_used(inventory: &[f64], s: &Structure) -> f64 {
+    inventory.iter().zip(&s.goods).map(|(q, g)| q * g.volume).sum()
+}
+
+pub fn market_value(portfolio: &[f64], prices: &[f64]) -> f64 {
+    portfolio.iter().zip(prices).map(|(q, p)| q * p).sum()
+}
@@ -0,0 +1,119 @@
+use crate::data::compiled::{IndexDef, IndexWeighting, Structure};
+use crate::data::ids::{IndexId, StockId};
+use crate::simulation::stock_market::StockMarket;
+use std::collections::VecDeque;
+
+pub const INDEX_HISTORY_LEN: usize = 600;
+
+/// Index levels are chain-linked: each tick the level moves by the weighted
+/// price change of members listed on both ticks, so listings, share issues and
+/// splits never cause jumps.
+#[derive(Debug, Clone)]
+pub struct StockIndices {
+    pub value: Vec<f64>,
+    pub prev_value: Vec<f64>,
+    pub history: Vec<VecDeque<f64>>,
+    pub prev_price: Vec<f64>,
+}
+
+impl StockIndices {
+    pub fn new(s: &Structure, sm: &StockMarket) -> Self {
+        let value: Vec<f64> = s.indices.iter().map(|ix| ix.base_value).collect();
+        let mut history = vec![VecDeque::with_capacity(INDEX_HISTORY_LEN); s.indices.len()];
+        for (h, &v) in history.iter_mut().zip(&value) {
+            h.push_back(v);
+        }
+        let mut out = Self {
+            prev_value: value.clone(),
+            value,
+            history,
+            prev_price: vec![0.0; s.stocks.len()],
+        };
+        out.snapshot_prices(sm);
+        out
+    }
+
+    fn snapshot_prices(&mut self, sm: &StockMarket) {
+        for (i, p) in self.prev_price.iter_mut().enumerate() {
+            *p = if sm.listed[i] { sm.price[i] } else { 0.0 };
+        }
+    }
+
+    pub fn on_split(&mut self, id: StockId, ratio: f64) {
+        self.prev_price[id.0 as usize] /= ratio;
+    }
+
+    pub fn update(&mut self, s: &Structure, sm: &StockMarket) {
+        for ix in &s.indices {
+            let i = ix.id.0 as usize;
+            let (mut num, mut den) = (0.0, 0.0);
+            for &sid in &ix.members {
+                let si = sid.0 as usize;
+                let p0 = self.prev_price[si];
+                if !sm.listed[si] || p0 <= 0.0 {
+                    continue;
+                }
+                let p1 = sm.price[si];
+                match ix.weighting {
+                    IndexWeighting::Price => {
+                        num += p1;
+                        den += p0;
+                    }
+                    IndexWeighting::Cap => {
+                        num += p1 * sm.outstanding[si];
+                        den += p0 * sm.outstanding[si];
+                    }
+                    IndexWeighting::Equal => {
+                        num += p1 / p0;
+                        den += 1.0;
+                    }
+                }
+            }
+
+            self.prev_value[i] = self.value[i];
+            if den > 0.0 {
+                self.value[i] *= num / den;
+            }
+            let h = &mut self.history[i];
+            if h.len() == INDEX_HISTORY_LEN {
+                h.pop_front();
+            }
+            h.push_back(self.value[i]);
+        }
+        self.snapshot_prices(sm);
+    }
+
+    pub fn change(&self, id: IndexId) -> f64 {
+        let i = id.0 as usize;
+        if self.prev_value[i] > 0.0 {
+            self.value[i] / self.prev_value[i] - 1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Fraction of an index-tracking investment that goes into each listed member.
+    pub fn weights(ix: &IndexDef, sm: &StockMarket) -> Vec<(StockId, f64)> {
+        let mut out: Vec<(StockId, f64)> = ix
+            .members
+            .iter()
+            .filter(|sid| sm.listed[sid.0 as usize])
+            .map(|&sid| {
+                let i = sid.0 as usize;
+                let w = match ix.weighting {
+                    IndexWeighting::Price => sm.price[i],
+                    IndexWeighting::Cap => sm.price[i] * sm.outstanding[i],
+                    IndexWeighting::Equal => 1.0,
+                };
+                (sid, w)
+            })
+            .collect();
+        let total: f64 = out.iter().map(|(_, w)| w).sum();
+        if total > 0.0 {
+            for (_, w) in &mut out {
+                *w /= total;
+            }
+        }
+        out
+    }
+}
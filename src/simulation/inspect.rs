@@ -0,0 +1,180 @@
+// src/simulation/inspect.rs
+//! History of a single household for the inspector. The engine records it for
+//! the inspected household only, as it ticks; nothing in the simulation reads
+//! it back, so it is left out of the state hash.
+
+use crate::data::compiled::Structure;
+use crate::data::ids::{GoodId, NeedId, StockId};
+use crate::simulation::events::EventLog;
+use crate::simulation::household::{HouseholdId, Households};
+use std::collections::VecDeque;
+
+/// Ticks of cash and utility kept for the sparklines.
+pub const HISTORY_LEN: usize = 240;
+/// Entries kept in the timeline.
+pub const TIMELINE_LEN: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Activity {
+    Bought { good: GoodId, qty: f64, cost: f64 },
+    Consumed { good: GoodId, qty: f64 },
+    MissedNeed { need: NeedId },
+    /// Negative shares are sales or short sales.
+    Traded { stock: StockId, shares: f64, price: f64 },
+    /// Cash moved outside the household's own spending and trading: wages,
+    /// dividends, margin interest, borrow fees and insolvency settlements.
+    Transfer { amount: f64 },
+    /// A simulation event naming this household, already described.
+    Event(String),
+    /// The household went bankrupt and left the simulation; nothing follows.
+    Removed,
+}
+
+impl Activity {
+    pub fn describe(&self, s: &Structure) -> String {
+        match self {
+            Activity::Bought { good, qty, cost } => format!("bought {:.2} {} for {:.2}", qty, s.good_name(*good), cost),
+            Activity::Consumed { good, qty } => format!("consumed {:.2} {}", qty, s.good_name(*good)),
+            Activity::MissedNeed { need } => format!("missed {}", s.need_ids.vec[need.0 as usize]),
+            Activity::Traded { stock, shares, price } if *shares >= 0.0 => {
+                format!("bought {:.2} {} at {:.2}", shares, s.stock_name(*stock), price)
+            }
+            Activity::Traded { stock, shares, price } => format!("sold {:.2} {} at {:.2}", -shares, s.stock_name(*stock), price),
+            Activity::Transfer { amount } => format!("transfer {:+.2}", amount),
+            Activity::Event(text) => text.clone(),
+            Activity::Removed => "removed from the simulation".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Inspector {
+    pub household: HouseholdId,
+    pub cash: VecDeque<f64>,
+    pub utility: VecDeque<f64>,
+    pub timeline: VecDeque<(u64, Activity)>,
+    /// Times in a row each need slot was met when it came due, counted since
+    /// the household was first inspected.
+    pub streaks: Vec<u32>,
+    due: Vec<bool>,
+    portfolio: Vec<f64>,
+    cash_before_trading: f64,
+    /// Cash after the household's own phase, plus trading.
+    expected_cash: f64,
+}
+
+impl Inspector {
+    pub fn new(household: HouseholdId) -> Self {
+        Self {
+            household,
+            cash: VecDeque::new(),
+            utility: VecDeque::new(),
+            timeline: VecDeque::new(),
+            streaks: Vec::new(),
+            due: Vec::new(),
+            portfolio: Vec::new(),
+            cash_before_trading: 0.0,
+            expected_cash: 0.0,
+        }
+    }
+
+    /// The inspector to keep across a migration. Timeline entries and need
+    /// streaks refer to goods, needs and stocks by their old ids, so only the
+    /// cash and utility history carries over.
+    pub(crate) fn migrated(self) -> Self {
+        Self { cash: self.cash, utility: self.utility, ..Self::new(self.household) }
+    }
+
+    /// Finds the household's row and notes which needs come due this tick.
+    pub(crate) fn begin(&mut self, hh: &Households) -> Option<usize> {
+        let idx = hh.position(self.household)?;
+        let h = hh.get(idx);
+        self.due = h.next_due_in.iter().map(|&t| t == 0).collect();
+        if self.streaks.len() != self.due.len() {
+            self.streaks = vec![0; self.due.len()];
+        }
+        Some(idx)
+    }
+
+    /// Records needs met and missed and the goods bought, given as amounts added
+    /// to the inventory during purchasing.
+    pub(crate) fn after_needs(&mut self, s: &Structure, hh: &Households, idx: usize, tick: u64, prices: &[f64], bought: &[(GoodId, f64)]) {
+        let h = hh.get(idx);
+        for &(good, qty) in h.last_consumed {
+            self.push(tick, Activity::Consumed { good, qty });
+        }
+        for (slot, &nid) in s.household_types[h.kind.0 as usize].needs.iter().enumerate() {
+            if !self.due.get(slot).copied().unwrap_or(false) {
+                continue;
+            }
+            if h.fulfilled_last[slot] {
+                self.streaks[slot] += 1;
+            } else {
+                self.streaks[slot] = 0;
+                self.push(tick, Activity::MissedNeed { need: nid });
+            }
+        }
+        for &(good, qty) in bought {
+            self.push(tick, Activity::Bought { good, qty, cost: qty * prices[good.0 as usize] });
+        }
+        self.expected_cash = h.cash;
+    }
+
+    pub(crate) fn before_trading(&mut self, hh: &Households, idx: usize) {
+        let h = hh.get(idx);
+        self.portfolio = h.portfolio.to_vec();
+        self.cash_before_trading = h.cash;
+    }
+
+    pub(crate) fn after_trading(&mut self, hh: &Households, idx: usize, tick: u64, stock_prices: &[f64]) {
+        let h = hh.get(idx);
+        let held = std::mem::take(&mut self.portfolio);
+        for (i, (&after, &before)) in h.portfolio.iter().zip(&held).enumerate() {
+            if (after - before).abs() > 1e-9 {
+                self.push(tick, Activity::Traded { stock: StockId(i as u32), shares: after - before, price: stock_prices[i] });
+            }
+        }
+        self.expected_cash += h.cash - self.cash_before_trading;
+    }
+
+    /// Records the remaining cash movement and events, and extends the history.
+    /// Insolvencies run after the household's own phase may have removed it or
+    /// shifted its row, so it is looked up again here.
+    pub(crate) fn finish(&mut self, s: &Structure, hh: &Households, tick: u64, events: &EventLog) {
+        let idx = hh.position(self.household);
+        if let Some(idx) = idx {
+            let transfer = hh.cash[idx] - self.expected_cash;
+            if transfer.abs() > 1e-9 {
+                self.push(tick, Activity::Transfer { amount: transfer });
+            }
+        }
+        let mine: Vec<String> = events
+            .events
+            .iter()
+            .rev()
+            .take_while(|e| e.tick == tick)
+            .filter(|e| e.kind.household() == Some(self.household))
+            .map(|e| e.describe(s))
+            .collect();
+        for text in mine.into_iter().rev() {
+            self.push(tick, Activity::Event(text));
+        }
+        let Some(idx) = idx else {
+            self.push(tick, Activity::Removed);
+            return;
+        };
+        for (series, v) in [(&mut self.cash, hh.cash[idx]), (&mut self.utility, hh.utility[idx])] {
+            if series.len() == HISTORY_LEN {
+                series.pop_front();
+            }
+            series.push_back(v);
+        }
+    }
+
+    fn push(&mut self, tick: u64, activity: Activity) {
+        if self.timeline.len() == TIMELINE_LEN {
+            self.timeline.pop_front();
+        }
+        self.timeline.push_back((tick, activity));
+    }
+}
@@ -0,0 +1,59 @@
+use std::ops::AddAssign;
+
+/// Household cash movements during the current tick, by cause. Their sum is
+/// the change in total household cash, which the integration tests check.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CashFlows {
+    pub income: f64,
+    /// Paid by production rules for the workers they employ.
+    pub wages: f64,
+    /// Goods bought for needs (negative) and inventory liquidated in bankruptcy.
+    pub goods: f64,
+    /// Paid to longs, charged to shorts.
+    pub dividends: f64,
+    /// Share trades, cash in lieu on splits and recoveries from failed firms.
+    pub securities: f64,
+    /// Paid by firms for shares they bought back from households.
+    pub buybacks: f64,
+    /// Margin loans drawn minus repayments.
+    pub margin: f64,
+    pub borrow_fees: f64,
+    /// Storage costs households paid for the goods they hold.
+    pub storage: f64,
+    /// Negative cash forgiven in bankruptcy.
+    pub write_offs: f64,
+    /// Cash of households removed or reset after bankruptcy.
+    pub replaced: f64,
+}
+
+impl CashFlows {
+    pub fn total(&self) -> f64 {
+        self.income
+            + self.wages
+            + self.goods
+            + self.dividends
+            + self.securities
+            + self.buybacks
+            + self.margin
+            + self.borrow_fees
+            + self.storage
+            + self.write_offs
+            + self.replaced
+    }
+}
+
+impl AddAssign for CashFlows {
+    fn add_assign(&mut self, o: Self) {
+        self.income += o.income;
+        self.wages += o.wages;
+        self.goods += o.goods;
+        self.dividends += o.dividends;
+        self.securities += o.securities;
+        self.buybacks += o.buybacks;
+        self.margin += o.margin;
+        self.borrow_fees += o.borrow_fees;
+        self.storage += o.storage;
+        self.write_offs += o.write_offs;
+        self.replaced += o.replaced;
+    }
+}
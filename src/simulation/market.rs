@@ -40,6 +40,11 @@ impl Market {
         self.supply[g.0 as usize] += amount.max(0.0);
     }
 
+    /// Supply offered so far this tick beyond the demand for it.
+    pub fn unsold(&self, g: GoodId) -> f64 {
+        (self.supply[g.0 as usize] - self.demand[g.0 as usize]).max(0.0)
+    }
+
     pub fn adjust_prices(&mut self) {
         for i in 0..self.price.len() {
             let d = self.demand[i];
@@ -0,0 +1,282 @@
+//! Moves a running [`Simulation`] onto a recompiled [`Structure`], e.g. after
+//! the data files were edited. Entries are matched by their string id, so
+//! reordering or inserting definitions keeps every household's holdings.
+//! Live prices follow edits to `base_price` proportionally; need timers are
+//! clamped to edited intervals. The inspector keeps only its cash and utility
+//! history.
+
+use crate::data::compiled::Structure;
+use crate::data::ids::{Interner, ShockId, StockId};
+use crate::simulation::engine::Simulation;
+use crate::simulation::events::EventKind;
+use crate::simulation::indices::StockIndices;
+use crate::simulation::inspect::Inspector;
+use crate::simulation::market::Market;
+use crate::simulation::production::Production;
+use crate::simulation::stock_market::StockMarket;
+use anyhow::bail;
+use std::fmt;
+
+/// Index correspondence between the old and new interner of one id kind.
+#[derive(Debug, Clone)]
+pub struct IdMap {
+    pub old_to_new: Vec<Option<u32>>,
+    pub new_to_old: Vec<Option<u32>>,
+}
+
+impl IdMap {
+    pub fn new(old: &Interner, new: &Interner) -> Self {
+        Self {
+            old_to_new: old.vec.iter().map(|k| new.map.get(k).copied()).collect(),
+            new_to_old: new.vec.iter().map(|k| old.map.get(k).copied()).collect(),
+        }
+    }
+
+    pub fn new_index(&self, old: usize) -> Option<usize> {
+        self.old_to_new[old].map(|i| i as usize)
+    }
+
+    pub fn old_index(&self, new: usize) -> Option<usize> {
+        self.new_to_old[new].map(|i| i as usize)
+    }
+
+    /// Builds a vector in the new order, taking entries that survived from `old`
+    /// and the rest from `fresh`.
+    pub fn remap<T: Clone>(&self, old: &[T], fresh: &[T]) -> Vec<T> {
+        (0..self.new_to_old.len())
+            .map(|i| match self.old_index(i) {
+                Some(o) => old[o].clone(),
+                None => fresh[i].clone(),
+            })
+            .collect()
+    }
+
+    fn diff(&self, kind: &str, old: &Interner, new: &Interner, report: &mut MigrationReport) {
+        for (i, n) in self.new_to_old.iter().enumerate() {
+            if n.is_none() {
+                report.added.push(format!("{} {}", kind, new.vec[i]));
+            }
+        }
+        for (i, n) in self.old_to_new.iter().enumerate() {
+            if n.is_none() {
+                report.removed.push(format!("{} {}", kind, old.vec[i]));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} added, {} removed", self.added.len(), self.removed.len())?;
+        for n in &self.notes {
+            write!(f, "; {}", n)?;
+        }
+        Ok(())
+    }
+}
+
+impl Simulation {
+    /// Switches to `new`, carrying over households, prices and market state.
+    /// Changes that would strand live state — removing a household type that
+    /// households belong to, or a stock somebody holds — are reported as an
+    /// error and leave the simulation untouched.
+    pub fn migrate(&mut self, new: Structure) -> anyhow::Result<MigrationReport> {
+        let old = &self.structure;
+        let goods = IdMap::new(&old.good_ids, &new.good_ids);
+        let stocks = IdMap::new(&old.stock_ids, &new.stock_ids);
+        let kinds = IdMap::new(&old.household_type_ids, &new.household_type_ids);
+        let indices = IdMap::new(&old.index_ids, &new.index_ids);
+        let rules = IdMap::new(&old.rule_ids, &new.rule_ids);
+        let shocks = IdMap::new(&old.shock_ids, &new.shock_ids);
+
+        let mut problems = Vec::new();
+        let mut members = vec![0usize; old.household_types.len()];
+        for &k in &self.households.kind {
+            members[k.0 as usize] += 1;
+        }
+        for (k, &n) in members.iter().enumerate() {
+            if n > 0 && kinds.new_index(k).is_none() {
+                problems.push(format!("household_type {} removed but {} households belong to it", old.household_type_ids.vec[k], n));
+            }
+        }
+        for st in &old.stocks {
+            let i = st.id.0 as usize;
+            if stocks.new_index(i).is_some() {
+                continue;
+            }
+            let (long, short) = self.households.iter().fold((0.0, 0.0), |(l, s), h| {
+                let q = h.shares(StockId(i as u32));
+                (l + q.max(0.0), s + (-q).max(0.0))
+            });
+            if long > 0.0 || short > 0.0 {
+                problems.push(format!("stock {} removed but households hold {:.0} shares long and {:.0} short", old.stock_ids.vec[i], long, short));
+            }
+        }
+        if !problems.is_empty() {
+            bail!("incompatible changes:\n  {}", problems.join("\n  "));
+        }
+
+        let mut report = MigrationReport::default();
+        goods.diff("good", &old.good_ids, &new.good_ids, &mut report);
+        IdMap::new(&old.need_ids, &new.need_ids).diff("need", &old.need_ids, &new.need_ids, &mut report);
+        kinds.diff("household_type", &old.household_type_ids, &new.household_type_ids, &mut report);
+        rules.diff("rule", &old.rule_ids, &new.rule_ids, &mut report);
+        shocks.diff("shock", &old.shock_ids, &new.shock_ids, &mut report);
+        stocks.diff("stock", &old.stock_ids, &new.stock_ids, &mut report);
+        indices.diff("index", &old.index_ids, &new.index_ids, &mut report);
+
+        let dropped: f64 = (0..old.goods.len())
+            .filter(|&g| goods.new_index(g).is_none())
+            .map(|g| self.households.iter().map(|h| h.inventory[g]).sum::<f64>())
+            .sum();
+        if dropped > 0.0 {
+            report.notes.push(format!("{:.1} units of removed goods dropped from inventories", dropped));
+        }
+
+        // Need slots follow their need id within the (possibly reordered) archetype list.
+        let slots: Vec<Vec<Option<usize>>> = new
+            .household_types
+            .iter()
+            .map(|td| {
+                let Some(ok) = kinds.old_index(td.id.0 as usize) else { return vec![None; td.needs.len()] };
+                let old_needs = &old.household_types[ok].needs;
+                td.needs
+                    .iter()
+                    .map(|n| {
+                        let key = &new.need_ids.vec[n.0 as usize];
+                        old_needs.iter().position(|o| &old.need_ids.vec[o.0 as usize] == key)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let fresh = Market::new(&new);
+        let mut market = Market {
+            price: goods.remap(&self.market.price, &fresh.price),
+            demand: goods.remap(&self.market.demand, &fresh.demand),
+            supply: goods.remap(&self.market.supply, &fresh.supply),
+            adjustment_rate: self.market.adjustment_rate,
+        };
+        for g in &new.goods {
+            let i = g.id.0 as usize;
+            if let Some(o) = goods.old_index(i) {
+                market.price[i] = (market.price[i] * rescale(old.goods[o].base_price, g.base_price)).max(0.01);
+            }
+        }
+
+        let fresh = StockMarket::new(&new);
+        let sm = &self.stock_market;
+        let mut stock_market = StockMarket {
+            price: stocks.remap(&sm.price, &fresh.price),
+            demand: stocks.remap(&sm.demand, &fresh.demand),
+            supply: stocks.remap(&sm.supply, &fresh.supply),
+            earnings: stocks.remap(&sm.earnings, &fresh.earnings),
+            dividend_due: stocks.remap(&sm.dividend_due, &fresh.dividend_due),
+            last_dividend: stocks.remap(&sm.last_dividend, &fresh.last_dividend),
+            discount_rate: sm.discount_rate,
+            value_pull: sm.value_pull,
+            outstanding: stocks.remap(&sm.outstanding, &fresh.outstanding),
+            held: stocks.remap(&sm.held, &fresh.held),
+            treasury: stocks.remap(&sm.treasury, &fresh.treasury),
+            short_interest: stocks.remap(&sm.short_interest, &fresh.short_interest),
+            listed: stocks.remap(&sm.listed, &fresh.listed),
+            loss_streak: stocks.remap(&sm.loss_streak, &fresh.loss_streak),
+            bankrupt: stocks.remap(&sm.bankrupt, &fresh.bankrupt),
+        };
+        for st in &new.stocks {
+            let i = st.id.0 as usize;
+            if let Some(o) = stocks.old_index(i) {
+                stock_market.price[i] = (stock_market.price[i] * rescale(old.stocks[o].base_price, st.base_price)).max(0.01);
+            }
+        }
+
+        let fresh = StockIndices::new(&new, &stock_market);
+        let ix = &self.indices;
+        let indices = StockIndices {
+            value: indices.remap(&ix.value, &fresh.value),
+            prev_value: indices.remap(&ix.prev_value, &fresh.prev_value),
+            history: indices.remap(&ix.history, &fresh.history),
+            prev_price: stocks.remap(&ix.prev_price, &fresh.prev_price),
+        };
+
+        // Capital follows its good within each surviving rule; new items start full.
+        let fresh = Production::new(&new);
+        let pr = &self.production;
+        let capital = new
+            .production_rules
+            .iter()
+            .map(|r| {
+                let i = r.id.0 as usize;
+                let Some(o) = rules.old_index(i) else { return fresh.capital[i].clone() };
+                let old_capital = &old.production_rules[o].capital;
+                r.capital
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &(g, _))| {
+                        let held = old_capital.iter().position(|&(og, _)| goods.new_index(og.0 as usize) == Some(g.0 as usize));
+                        held.map_or(fresh.capital[i][k], |ok| pr.capital[o][ok])
+                    })
+                    .collect()
+            })
+            .collect();
+        let production = Production {
+            shock_until: shocks.remap(&pr.shock_until, &fresh.shock_until),
+            last_multiplier: rules.remap(&pr.last_multiplier, &fresh.last_multiplier),
+            stock: goods.remap(&pr.stock, &fresh.stock),
+            capital,
+            utilization: rules.remap(&pr.utilization, &fresh.utilization),
+            // Bottlenecks name goods by old id; they are refilled on the next run.
+            bottleneck: fresh.bottleneck,
+            labor_supply: pr.labor_supply,
+            labor_used: pr.labor_used,
+            wages: pr.wages,
+        };
+
+        let (households, over) = self.households.migrate(&new, &goods, &stocks, &kinds, &slots);
+        if over > 0.0 {
+            report.notes.push(format!("{:.1} units over storage capacity dropped from inventories", over));
+        }
+        self.households = households;
+        self.inspector = self.inspector.take().map(Inspector::migrated);
+        self.events.events.retain_mut(|e| match &mut e.kind {
+            EventKind::FirmBankruptcy { stock, .. } => match stocks.new_index(stock.0 as usize) {
+                Some(n) => {
+                    *stock = StockId(n as u32);
+                    true
+                }
+                None => false,
+            },
+            EventKind::SupplyShock { shock, .. } => match shocks.new_index(shock.0 as usize) {
+                Some(n) => {
+                    *shock = ShockId(n as u32);
+                    true
+                }
+                None => false,
+            },
+            _ => true,
+        });
+        self.market = market;
+        self.production = production;
+        self.stock_market = stock_market;
+        self.indices = indices;
+        self.structure = new;
+        Ok(report)
+    }
+}
+
+/// Factor by which a live price follows an edited base price.
+fn rescale(old_base: f64, new_base: f64) -> f64 {
+    if old_base > 0.0 && new_base > 0.0 {
+        new_base / old_base
+    } else {
+        1.0
+    }
+}
+
@@ -1,6 +1,14 @@
 // src/simulation/mod.rs
 pub mod economy;
 pub mod engine;
+pub mod events;
+pub mod hash;
 pub mod household;
+pub mod indices;
+pub mod inspect;
+pub mod ledger;
 pub mod market;
+pub mod migrate;
+pub mod production;
+pub mod query;
 pub mod stock_market;
@@ -0,0 +1,193 @@
+//! Economy-wide production. Every rule runs up to `capacity` times per `ticks`,
+//! buying its inputs from and selling its outputs to the goods market, so
+//! output swings show up as price moves. Output is scaled by the rule's
+//! seasonal factor, a random yield and any active shocks.
+//!
+//! Intermediate goods skip the market: rules run upstream first and leave them
+//! in a shared stock for the rules that use them, so a shortfall upstream caps
+//! the runs downstream. Capital goods are held by each rule and replaced from
+//! that stock or from what the market was offered as they depreciate. Labor
+//! comes from households, who are paid a wage for it.
+
+use crate::data::compiled::{ProductionRuleDef, Structure, YieldDist};
+use crate::data::ids::{GoodId, ShockId};
+use crate::simulation::market::Market;
+use rand::Rng;
+use std::f64::consts::TAU;
+
+/// What kept a rule below capacity on its last run.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bottleneck {
+    None,
+    Labor,
+    Capital(GoodId),
+    Input(GoodId),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Production {
+    /// Tick at which each shock ends, `None` while it is inactive.
+    pub shock_until: Vec<Option<u64>>,
+    /// Output multiplier of each rule's most recent run.
+    pub last_multiplier: Vec<f64>,
+    /// Intermediate goods waiting for the rules that use them, by good.
+    pub stock: Vec<f64>,
+    /// Capital held by each rule, parallel to its `capital` list.
+    pub capital: Vec<Vec<f64>>,
+    /// Runs of each rule's last run as a share of its capacity.
+    pub utilization: Vec<f64>,
+    pub bottleneck: Vec<Bottleneck>,
+    /// Workers households offered and rules took this tick.
+    pub labor_supply: f64,
+    pub labor_used: f64,
+    /// Wages owed for this tick's employment, paid out to households by the engine.
+    pub wages: f64,
+}
+
+impl Production {
+    /// Rules start fully equipped with capital.
+    pub fn new(s: &Structure) -> Self {
+        let n = s.production_rules.len();
+        Self {
+            shock_until: vec![None; s.shocks.len()],
+            last_multiplier: vec![1.0; n],
+            stock: vec![0.0; s.goods.len()],
+            capital: s.production_rules.iter().map(|r| r.capital.iter().map(|&(_, q)| q * r.capacity).collect()).collect(),
+            utilization: vec![1.0; n],
+            bottleneck: vec![Bottleneck::None; n],
+            labor_supply: 0.0,
+            labor_used: 0.0,
+            wages: 0.0,
+        }
+    }
+
+    pub fn is_active(&self, id: ShockId) -> bool {
+        self.shock_until[id.0 as usize].is_some()
+    }
+
+    /// Ends expired shocks and rolls for new ones; returns the shocks that started.
+    pub fn step_shocks<R: Rng>(&mut self, s: &Structure, tick: u64, rng: &mut R) -> Vec<ShockId> {
+        let mut started = Vec::new();
+        for sh in &s.shocks {
+            let slot = &mut self.shock_until[sh.id.0 as usize];
+            match *slot {
+                Some(until) if tick >= until => *slot = None,
+                Some(_) => {}
+                None if sh.chance > 0.0 && rng.gen::<f64>() < sh.chance => {
+                    *slot = Some(tick + sh.duration);
+                    started.push(sh.id);
+                }
+                None => {}
+            }
+        }
+        started
+    }
+
+    /// Output multiplier for a run of `rule` this tick. Draws from `rng` only for random yields.
+    pub fn multiplier<R: Rng>(&self, s: &Structure, rule: &ProductionRuleDef, tick: u64, rng: &mut R) -> f64 {
+        let season = rule.seasonality.as_ref().map_or(1.0, |sx| sx.factor(tick));
+        let draw = match rule.yield_dist {
+            YieldDist::Fixed => 1.0,
+            YieldDist::Normal { mean, sd } => {
+                // Box-Muller; 1 - u keeps the logarithm finite.
+                let (u1, u2): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+                mean + sd * (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+            }
+            YieldDist::Uniform { min, max } => {
+                if max > min {
+                    rng.gen_range(min..max)
+                } else {
+                    min
+                }
+            }
+        };
+        let shocks: f64 = s.shocks.iter().filter(|sh| self.is_active(sh.id) && sh.affects(rule.id)).map(|sh| sh.multiplier).product();
+        (season * draw * shocks).max(0.0)
+    }
+
+    /// Runs every rule due at `tick` in supply chain order with `labor` workers on offer.
+    pub fn run<R: Rng>(&mut self, s: &Structure, tick: u64, labor: f64, market: &mut Market, rng: &mut R) {
+        for (g, q) in self.stock.iter_mut().enumerate() {
+            *q *= 1.0 - s.goods[g].decay_rate;
+        }
+        self.labor_supply = labor;
+        self.labor_used = 0.0;
+        self.wages = 0.0;
+        let mut labor_left = labor;
+        for &rid in &s.rule_order {
+            let r = &s.production_rules[rid.0 as usize];
+            let ri = rid.0 as usize;
+
+            // Workers stay employed between runs; upstream rules hire first.
+            let needed = r.labor * r.capacity;
+            let labor_share = if needed > 0.0 { (labor_left / needed).min(1.0) } else { 1.0 };
+            labor_left -= needed * labor_share;
+            self.labor_used += needed * labor_share;
+            self.wages += needed * labor_share * r.wage;
+
+            for (k, &(g, q)) in r.capital.iter().enumerate() {
+                let held = &mut self.capital[ri][k];
+                *held *= 1.0 - s.goods[g.0 as usize].decay_rate;
+                let short = q * r.capacity - *held;
+                if short <= 0.0 {
+                    continue;
+                }
+                if s.intermediate[g.0 as usize] {
+                    let take = short.min(self.stock[g.0 as usize]);
+                    self.stock[g.0 as usize] -= take;
+                    *held += take;
+                } else {
+                    // Only what was offered this tick and nobody else bought.
+                    let take = short.min(market.unsold(g));
+                    market.note_demand(g, take);
+                    *held += take;
+                }
+            }
+
+            if !tick.is_multiple_of(r.ticks) || r.capacity <= 0.0 {
+                continue;
+            }
+            let m = self.multiplier(s, r, tick, rng);
+            self.last_multiplier[ri] = m;
+
+            let mut runs = r.capacity;
+            let mut bottleneck = Bottleneck::None;
+            let mut limit = |cap: f64, why: Bottleneck| {
+                // Tolerate rounding left over from topping capital up to its target.
+                if cap < runs * (1.0 - 1e-9) {
+                    runs = cap;
+                    bottleneck = why;
+                }
+            };
+            limit(r.capacity * labor_share, Bottleneck::Labor);
+            for (k, &(g, q)) in r.capital.iter().enumerate() {
+                if q > 0.0 {
+                    limit(self.capital[ri][k] / q, Bottleneck::Capital(g));
+                }
+            }
+            for &(g, q) in &r.inputs {
+                if q > 0.0 && s.intermediate[g.0 as usize] {
+                    limit(self.stock[g.0 as usize] / q, Bottleneck::Input(g));
+                }
+            }
+            let runs = runs.max(0.0);
+
+            for &(g, q) in &r.inputs {
+                if s.intermediate[g.0 as usize] {
+                    self.stock[g.0 as usize] = (self.stock[g.0 as usize] - runs * q).max(0.0);
+                } else {
+                    market.note_demand(g, runs * q);
+                }
+            }
+            for &(g, q) in &r.outputs {
+                if s.intermediate[g.0 as usize] {
+                    self.stock[g.0 as usize] += runs * q * m;
+                } else {
+                    market.note_supply(g, runs * q * m);
+                }
+            }
+            self.utilization[ri] = runs / r.capacity;
+            self.bottleneck[ri] = bottleneck;
+        }
+    }
+}
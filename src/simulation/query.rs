@@ -0,0 +1,210 @@
+// src/simulation/query.rs
+//! Household queries for browsing large populations: a filter such as
+//! `type=poor cash<50` plus a sort column, evaluated to household ids.
+
+use crate::data::compiled::Structure;
+use crate::simulation::engine::Simulation;
+use crate::simulation::household::{HouseholdId, HouseholdRef};
+use anyhow::{anyhow, bail};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SortKey {
+    Id,
+    Type,
+    Cash,
+    Utility,
+    NetWorth,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 5] = [SortKey::Id, SortKey::Type, SortKey::Cash, SortKey::Utility, SortKey::NetWorth];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Id => "id",
+            SortKey::Type => "type",
+            SortKey::Cash => "cash",
+            SortKey::Utility => "utility",
+            SortKey::NetWorth => "net worth",
+        }
+    }
+
+    pub fn next(self) -> SortKey {
+        let i = SortKey::ALL.iter().position(|&k| k == self).unwrap_or(0);
+        SortKey::ALL[(i + 1) % SortKey::ALL.len()]
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Field {
+    Id,
+    Cash,
+    Utility,
+    NetWorth,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn holds(self, a: f64, b: f64) -> bool {
+        match self {
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    /// Archetype id, matched exactly; resolved against the structure when run.
+    Type { id: String, equal: bool },
+    Number { field: Field, op: Op, value: f64 },
+}
+
+/// Space-separated conditions that must all hold, e.g. `type=poor cash<50`.
+/// Fields are `type`, `id`, `cash`, `utility` and `worth` (net worth).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    pub text: String,
+    conditions: Vec<Condition>,
+}
+
+impl Filter {
+    pub fn parse(text: &str, s: &Structure) -> anyhow::Result<Self> {
+        let mut conditions = Vec::new();
+        for term in text.split_whitespace() {
+            let at = term.find(['<', '>', '=', '!']).ok_or_else(|| anyhow!("filter term {} has no comparison", term))?;
+            let (name, rest) = term.split_at(at);
+            let (op, value) = [("<=", Op::Le), (">=", Op::Ge), ("!=", Op::Ne), ("<", Op::Lt), (">", Op::Gt), ("=", Op::Eq)]
+                .iter()
+                .find_map(|&(sym, op)| rest.strip_prefix(sym).map(|v| (op, v)))
+                .ok_or_else(|| anyhow!("filter term {} has an invalid comparison", term))?;
+            let field = match name {
+                "type" => {
+                    if !matches!(op, Op::Eq | Op::Ne) {
+                        bail!("filter term {}: type can only be compared with = or !=", term);
+                    }
+                    if !s.household_type_ids.map.contains_key(value) {
+                        bail!("filter term {}: unknown household type {}", term, value);
+                    }
+                    conditions.push(Condition::Type { id: value.to_string(), equal: op == Op::Eq });
+                    continue;
+                }
+                "id" => Field::Id,
+                "cash" => Field::Cash,
+                "utility" => Field::Utility,
+                "worth" => Field::NetWorth,
+                other => bail!("filter term {}: unknown field {}, expected type, id, cash, utility or worth", term, other),
+            };
+            let value = value.parse().map_err(|_| anyhow!("filter term {}: {} is not a number", term, value))?;
+            conditions.push(Condition::Number { field, op, value });
+        }
+        Ok(Self { text: text.trim().to_string(), conditions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    fn uses_net_worth(&self) -> bool {
+        self.conditions.iter().any(|c| matches!(c, Condition::Number { field: Field::NetWorth, .. }))
+    }
+}
+
+/// A filter and sort order over a simulation's households.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HouseholdQuery {
+    pub filter: Filter,
+    pub sort: SortKey,
+    pub descending: bool,
+}
+
+impl Default for HouseholdQuery {
+    fn default() -> Self {
+        Self { filter: Filter::default(), sort: SortKey::Id, descending: false }
+    }
+}
+
+impl HouseholdQuery {
+    /// Ids of the matching households in display order; ties keep id order.
+    pub fn run(&self, sim: &Simulation) -> Vec<HouseholdId> {
+        let s = &sim.structure;
+        let hh = &sim.households;
+        let types: Vec<(Option<u32>, bool)> = self
+            .filter
+            .conditions
+            .iter()
+            .filter_map(|c| match c {
+                Condition::Type { id, equal } => Some((s.household_type_ids.map.get(id).copied(), *equal)),
+                _ => None,
+            })
+            .collect();
+        let worth = self.sort == SortKey::NetWorth || self.filter.uses_net_worth();
+        let net_worth = |h: &HouseholdRef| if worth { h.net_worth(&sim.market.price, &sim.stock_market.price) } else { 0.0 };
+        // Archetypes sort by display name, so each gets its place in that order.
+        let mut by_name: Vec<usize> = (0..s.household_types.len()).collect();
+        by_name.sort_by(|&a, &b| s.household_types[a].display_name.cmp(&s.household_types[b].display_name));
+        let mut type_rank = vec![0.0; by_name.len()];
+        for (rank, &t) in by_name.iter().enumerate() {
+            type_rank[t] = rank as f64;
+        }
+
+        let mut rows: Vec<(f64, HouseholdId)> = Vec::new();
+        for i in 0..hh.len() {
+            let h = hh.get(i);
+            if !types.iter().all(|&(kind, equal)| (kind == Some(h.kind.0)) == equal) {
+                continue;
+            }
+            let nw = net_worth(&h);
+            let keep = self.filter.conditions.iter().all(|c| match *c {
+                Condition::Type { .. } => true,
+                Condition::Number { field, op, value } => op.holds(field_value(&h, field, nw), value),
+            });
+            if !keep {
+                continue;
+            }
+            let key = match self.sort {
+                SortKey::Id => h.id.0 as f64,
+                SortKey::Type => type_rank[h.kind.0 as usize],
+                SortKey::Cash => h.cash,
+                SortKey::Utility => h.utility,
+                SortKey::NetWorth => nw,
+            };
+            rows.push((key, h.id));
+        }
+        rows.sort_by(|a, b| {
+            let by_key = if self.descending { b.0.total_cmp(&a.0) } else { a.0.total_cmp(&b.0) };
+            by_key.then_with(|| a.1 .0.cmp(&b.1 .0))
+        });
+        rows.into_iter().map(|(_, id)| id).collect()
+    }
+}
+
+fn field_value(h: &HouseholdRef, field: Field, net_worth: f64) -> f64 {
+    match field {
+        Field::Id => h.id.0 as f64,
+        Field::Cash => h.cash,
+        Field::Utility => h.utility,
+        Field::NetWorth => net_worth,
+    }
+}
+
+/// Position in `rows` of the first household whose id starts with `prefix`.
+pub fn search_id(rows: &[HouseholdId], prefix: &str) -> Option<usize> {
+    if prefix.is_empty() {
+        return None;
+    }
+    rows.iter().position(|id| id.0.to_string().starts_with(prefix))
+}
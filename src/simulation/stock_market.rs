@@ -3,39 +3,165 @@
 
 use crate::data::compiled::Structure;
 use crate::data::ids::StockId;
+use rand::Rng;
 
 #[derive(Debug, Clone)]
 pub struct StockMarket {
     pub price: Vec<f64>,
     pub demand: Vec<f64>,
     pub supply: Vec<f64>,
+    pub earnings: Vec<f64>,
+    pub dividend_due: Vec<f64>,
+    pub last_dividend: Vec<f64>,
+    /// Return per dividend interval investors require; fair value is `earnings / discount_rate`.
+    pub discount_rate: f64,
+    pub value_pull: f64,
+    pub outstanding: Vec<f64>,
+    /// Sum of all household `Portfolio` holdings.
+    pub held: Vec<f64>,
+    pub treasury: Vec<f64>,
+    /// Shares currently sold short; borrowed from long holders and added to the float.
+    pub short_interest: Vec<f64>,
+    pub listed: Vec<bool>,
+    /// Consecutive dividend intervals with negative earnings.
+    pub loss_streak: Vec<u32>,
+    pub bankrupt: Vec<bool>,
 }
 
 impl StockMarket {
     pub fn new(s: &Structure) -> Self {
         let mut price = vec![0.0; s.stocks.len()];
+        let mut earnings = vec![0.0; s.stocks.len()];
+        let mut outstanding = vec![0.0; s.stocks.len()];
+        let mut listed = vec![false; s.stocks.len()];
         for st in &s.stocks {
-            price[st.id.0 as usize] = st.base_price.max(0.01);
+            let i = st.id.0 as usize;
+            price[i] = st.base_price.max(0.01);
+            earnings[i] = st.earnings_per_share;
+            outstanding[i] = st.shares_outstanding as f64;
+            listed[i] = st.ipo_tick().is_none();
         }
         let n = s.stocks.len();
         Self {
             price,
             demand: vec![0.0; n],
             supply: vec![0.0; n],
+            earnings,
+            dividend_due: vec![0.0; n],
+            last_dividend: vec![0.0; n],
+            discount_rate: 0.02,
+            value_pull: 0.01,
+            outstanding,
+            held: vec![0.0; n],
+            treasury: vec![0.0; n],
+            short_interest: vec![0.0; n],
+            listed,
+            loss_streak: vec![0; n],
+            bankrupt: vec![false; n],
         }
     }
 
     pub fn reset(&mut self) {
         self.demand.fill(0.0);
         self.supply.fill(0.0);
+        self.dividend_due.fill(0.0);
     }
 
     pub fn note_buy(&mut self, id: StockId, qty: f64) {
         self.demand[id.0 as usize] += qty.max(0.0);
+        self.held[id.0 as usize] += qty.max(0.0);
     }
 
     pub fn note_sell(&mut self, id: StockId, qty: f64) {
         self.supply[id.0 as usize] += qty.max(0.0);
+        self.held[id.0 as usize] -= qty.max(0.0);
+    }
+
+    pub fn note_short(&mut self, id: StockId, qty: f64) {
+        self.supply[id.0 as usize] += qty.max(0.0);
+        self.short_interest[id.0 as usize] += qty.max(0.0);
+    }
+
+    pub fn note_cover(&mut self, id: StockId, qty: f64) {
+        self.demand[id.0 as usize] += qty.max(0.0);
+        self.short_interest[id.0 as usize] = (self.short_interest[id.0 as usize] - qty.max(0.0)).max(0.0);
+    }
+
+    /// Shares neither held by households nor in treasury, i.e. what can still be bought.
+    pub fn available(&self, id: StockId) -> f64 {
+        let i = id.0 as usize;
+        (self.outstanding[i] + self.short_interest[i] - self.held[i] - self.treasury[i]).max(0.0)
+    }
+
+    /// Long holdings not yet lent out to short sellers.
+    pub fn lendable(&self, id: StockId) -> f64 {
+        let i = id.0 as usize;
+        (self.held[i] - self.short_interest[i]).max(0.0)
+    }
+
+    pub fn round_lot(&self, s: &Structure, id: StockId, qty: f64) -> f64 {
+        let lot = s.stocks[id.0 as usize].lot_size;
+        if lot > 0.0 {
+            (qty / lot).trunc() * lot
+        } else {
+            qty
+        }
+    }
+
+    /// Grants up to `qty` shares of the float without price pressure, as for
+    /// starting portfolios. Stocks not listed yet have no float to grant.
+    pub fn allocate(&mut self, s: &Structure, id: StockId, qty: f64) -> f64 {
+        if !self.listed[id.0 as usize] {
+            return 0.0;
+        }
+        let q = self.round_lot(s, id, qty.min(self.available(id))).max(0.0);
+        self.held[id.0 as usize] += q;
+        q
+    }
+
+    /// Earnings evolve once per dividend interval; the payout is left in `dividend_due` for this tick.
+    pub fn step_earnings<R: Rng>(&mut self, s: &Structure, tick: u64, rng: &mut R) {
+        for st in &s.stocks {
+            let i = st.id.0 as usize;
+            if self.bankrupt[i] || !tick.is_multiple_of(st.dividend_interval_ticks) {
+                continue;
+            }
+            let shock = if st.earnings_volatility > 0.0 { rng.gen_range(-1.0..1.0) * st.earnings_volatility } else { 0.0 };
+            self.earnings[i] = self.earnings[i] * (1.0 + st.earnings_growth) + shock * st.earnings_per_share;
+            if self.earnings[i] < 0.0 {
+                self.loss_streak[i] += 1;
+            } else {
+                self.loss_streak[i] = 0;
+            }
+
+            // Shares not yet floated pay nothing; earnings still accrue.
+            let dps = if self.listed[i] { self.earnings[i].max(0.0) * st.payout_ratio } else { 0.0 };
+            self.dividend_due[i] = dps;
+            self.last_dividend[i] = dps;
+        }
+    }
+
+    pub fn fair_value(&self, id: StockId) -> Option<f64> {
+        let e = self.earnings[id.0 as usize];
+        if e > 0.0 && self.discount_rate > 0.0 {
+            Some(e / self.discount_rate)
+        } else {
+            None
+        }
+    }
+
+    pub fn dividend_yield(&self, id: StockId) -> f64 {
+        let i = id.0 as usize;
+        self.last_dividend[i] / self.price[i]
+    }
+
+    pub fn pe_ratio(&self, id: StockId) -> Option<f64> {
+        let e = self.earnings[id.0 as usize];
+        if e > 0.0 {
+            Some(self.price[id.0 as usize] / e)
+        } else {
+            None
+        }
     }
 
     pub fn adjust(&mut self, s: &Structure) {
@@ -45,7 +171,11 @@ impl StockMarket {
             let sup = self.supply[i];
             let pressure = if d + sup <= 0.0 { 0.0 } else { (d - sup) / (d + sup) }.clamp(-0.8, 0.8);
             let vol = st.volatility.clamp(0.0, 1.0);
-            let next = self.price[i] * (1.0 + vol * pressure);
+            let anchor = match self.fair_value(st.id) {
+                Some(fair) => ((fair / self.price[i]).ln() * self.value_pull).clamp(-0.5, 0.5),
+                None => 0.0,
+            };
+            let next = self.price[i] * (1.0 + vol * pressure + anchor);
             self.price[i] = next.max(0.01);
         }
     }
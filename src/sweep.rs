@@ -0,0 +1,188 @@
+use crate::config::Config;
+use crate::data::compiled::Structure;
+use crate::data::overrides;
+use crate::data::xml::{self, RawXml};
+use crate::simulation::economy::EconomyMetrics;
+use crate::simulation::engine::Simulation;
+use anyhow::{bail, Context};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// Parameter grid read from TOML. Keys are `seed`, `start_households` or an
+/// XML field path understood by [`overrides::set_numeric`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepSpec {
+    pub ticks: u64,
+    /// Emit a row every this many ticks in addition to the final one; 0 keeps only the final row.
+    #[serde(default)]
+    pub sample_every: u64,
+    pub params: BTreeMap<String, ParamValues>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ParamValues {
+    List(Vec<ParamValue>),
+    Range { from: ParamValue, to: ParamValue, step: ParamValue },
+}
+
+/// A value as written in the spec. Whole numbers are kept exact, so seeds
+/// beyond 2^53 are not rounded.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ParamValue {
+    Int(u64),
+    Float(f64),
+}
+
+impl ParamValue {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            ParamValue::Int(v) => v as f64,
+            ParamValue::Float(v) => v,
+        }
+    }
+
+    fn as_u64(self, key: &str) -> anyhow::Result<u64> {
+        match self {
+            ParamValue::Int(v) => Ok(v),
+            ParamValue::Float(v) => bail!("parameter {} must be a non-negative whole number, got {}", key, v),
+        }
+    }
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamValue::Int(v) => write!(f, "{}", v),
+            ParamValue::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl ParamValues {
+    fn values(&self, key: &str) -> anyhow::Result<Vec<ParamValue>> {
+        match *self {
+            ParamValues::List(ref v) if v.is_empty() => bail!("parameter {} has no values", key),
+            ParamValues::List(ref v) => Ok(v.clone()),
+            ParamValues::Range { from: ParamValue::Int(from), to: ParamValue::Int(to), step: ParamValue::Int(step) } => {
+                if step == 0 || to < from {
+                    bail!("parameter {} needs from <= to and a positive step", key);
+                }
+                Ok((from..=to).step_by(step as usize).map(ParamValue::Int).collect())
+            }
+            ParamValues::Range { from, to, step } => {
+                let (from, to, step) = (from.as_f64(), to.as_f64(), step.as_f64());
+                if step <= 0.0 || to < from {
+                    bail!("parameter {} needs from <= to and a positive step", key);
+                }
+                let n = ((to - from) / step + 1e-9).floor() as usize;
+                Ok((0..=n).map(|i| ParamValue::Float(from + i as f64 * step)).collect())
+            }
+        }
+    }
+}
+
+impl SweepSpec {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let txt = std::fs::read_to_string(path).with_context(|| format!("reading sweep spec {}", path))?;
+        Self::parse(&txt).with_context(|| format!("parsing sweep spec {}", path))
+    }
+
+    pub fn parse(txt: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(txt)?)
+    }
+
+    /// Every combination of parameter values, the last key varying fastest.
+    pub fn runs(&self) -> anyhow::Result<Vec<Vec<(String, ParamValue)>>> {
+        let mut runs: Vec<Vec<(String, ParamValue)>> = vec![Vec::new()];
+        for (key, values) in &self.params {
+            let values = values.values(key)?;
+            runs = runs
+                .into_iter()
+                .flat_map(|run| {
+                    values.iter().map(move |&v| {
+                        let mut next = run.clone();
+                        next.push((key.clone(), v));
+                        next
+                    })
+                })
+                .collect();
+        }
+        Ok(runs)
+    }
+}
+
+fn run_one(cfg: &Config, raw: &RawXml, params: &[(String, ParamValue)], spec: &SweepSpec) -> anyhow::Result<Vec<EconomyMetrics>> {
+    let mut raw = raw.clone();
+    let mut seed = cfg.seed;
+    let mut households = cfg.start_households;
+    for (key, v) in params {
+        match key.as_str() {
+            "seed" => seed = v.as_u64(key)?,
+            "start_households" => households = v.as_u64(key)? as usize,
+            _ => overrides::set_numeric(&mut raw, key, v.as_f64())?,
+        }
+    }
+
+    let structure = Structure::compile(raw)?;
+    let mut sim = Simulation::new(structure, seed, households);
+    let mut samples = Vec::new();
+    for _ in 0..spec.ticks {
+        sim.tick();
+        if spec.sample_every > 0 && sim.tick.is_multiple_of(spec.sample_every) && sim.tick != spec.ticks {
+            samples.push(EconomyMetrics::compute(&sim));
+        }
+    }
+    samples.push(EconomyMetrics::compute(&sim));
+    Ok(samples)
+}
+
+/// Runs every combination in parallel and returns one CSV with a row per run and sampled tick.
+pub fn run(cfg: &Config, spec: &SweepSpec, threads: usize) -> anyhow::Result<String> {
+    let raw = xml::load_all(cfg)?;
+    let runs = spec.runs()?;
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let results: Vec<Vec<EconomyMetrics>> = pool.install(|| {
+        runs.par_iter()
+            .enumerate()
+            .map(|(id, params)| run_one(cfg, &raw, params, spec).with_context(|| format!("sweep run {}", id)))
+            .collect::<anyhow::Result<_>>()
+    })?;
+
+    let mut out = String::from("run_id");
+    for key in spec.params.keys() {
+        write!(out, ",{}", key).ok();
+    }
+    out.push_str(
+        ",tick,households,total_cash,avg_utility,avg_good_price,stock_market_value,total_margin_debt,cash_gini,margin_calls,household_bankruptcies,firm_bankruptcies\n",
+    );
+    for (id, (params, samples)) in runs.iter().zip(&results).enumerate() {
+        for m in samples {
+            write!(out, "{}", id).ok();
+            for (_, v) in params {
+                write!(out, ",{}", v).ok();
+            }
+            writeln!(
+                out,
+                ",{},{},{},{},{},{},{},{},{},{},{}",
+                m.tick,
+                m.households,
+                m.total_cash,
+                m.avg_utility,
+                m.avg_good_price,
+                m.stock_market_value,
+                m.total_margin_debt,
+                m.cash_gini,
+                m.margin_calls,
+                m.household_bankruptcies,
+                m.firm_bankruptcies
+            )
+            .ok();
+        }
+    }
+    Ok(out)
+}
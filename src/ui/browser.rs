@@ -0,0 +1,129 @@
+// src/ui/browser.rs
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::household::HouseholdId;
+use economy_sim::simulation::query::{search_id, Filter, HouseholdQuery};
+use crossterm::event::KeyCode;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Editing {
+    Filter,
+    Search,
+}
+
+/// Ticks between reruns of an unchanged query. Sorting every household each
+/// tick is too slow for large populations, and values a few ticks old are fine
+/// for browsing.
+const REFRESH_TICKS: u64 = 10;
+
+/// State of the Households tab: the query, its cached result and the cursor.
+/// Rows are recomputed when the query changes, households are removed, or
+/// every `REFRESH_TICKS` ticks, and only while the tab is shown.
+#[derive(Debug, Default)]
+pub struct HouseholdBrowser {
+    pub query: HouseholdQuery,
+    /// Household ids in display order.
+    pub rows: Vec<HouseholdId>,
+    /// Position of the selection within `rows`.
+    pub cursor: usize,
+    /// First visible row.
+    pub offset: usize,
+    /// Rows that fit on screen, set while rendering.
+    pub page: usize,
+    pub editing: Option<Editing>,
+    pub input: String,
+    pub error: Option<String>,
+    /// Tick and household count the rows were computed at.
+    refreshed: Option<(u64, usize)>,
+}
+
+impl HouseholdBrowser {
+    pub fn invalidate(&mut self) {
+        self.refreshed = None;
+    }
+
+    /// Reruns the query if the rows are due, keeping the selected household selected.
+    pub fn refresh(&mut self, sim: &Simulation, selected: &mut HouseholdId) {
+        if let Some((tick, count)) = self.refreshed {
+            if count == sim.households.len() && (tick..tick + REFRESH_TICKS).contains(&sim.tick) {
+                return;
+            }
+        }
+        self.refreshed = Some((sim.tick, sim.households.len()));
+        self.rows = self.query.run(sim);
+        match self.rows.iter().position(|&id| id == *selected) {
+            Some(pos) => self.cursor = pos,
+            None => self.cursor = self.cursor.min(self.rows.len().saturating_sub(1)),
+        }
+        if let Some(&id) = self.rows.get(self.cursor) {
+            *selected = id;
+        }
+    }
+
+    /// Moves the cursor by `delta` rows, clamped to the list.
+    pub fn move_by(&mut self, delta: isize, selected: &mut HouseholdId) {
+        if self.rows.is_empty() {
+            return;
+        }
+        self.cursor = self.cursor.saturating_add_signed(delta).min(self.rows.len() - 1);
+        *selected = self.rows[self.cursor];
+    }
+
+    /// Keeps the cursor within the `height` rows shown from `offset`.
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        self.page = height.max(1);
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + self.page {
+            self.offset = self.cursor + 1 - self.page;
+        }
+        self.offset = self.offset.min(self.rows.len().saturating_sub(self.page));
+    }
+
+    pub fn start(&mut self, editing: Editing) {
+        self.input = match editing {
+            Editing::Filter => self.query.filter.text.clone(),
+            Editing::Search => String::new(),
+        };
+        self.editing = Some(editing);
+        self.error = None;
+    }
+
+    /// Handles a key while the filter bar or search is open.
+    pub fn on_edit_key(&mut self, code: KeyCode, sim: &Simulation, selected: &mut HouseholdId) {
+        let Some(editing) = self.editing else { return };
+        match code {
+            KeyCode::Esc => {
+                self.editing = None;
+                self.error = None;
+            }
+            KeyCode::Enter if editing == Editing::Filter => match Filter::parse(&self.input, &sim.structure) {
+                Ok(filter) => {
+                    self.query.filter = filter;
+                    self.editing = None;
+                    self.error = None;
+                    self.invalidate();
+                    self.refresh(sim, selected);
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            KeyCode::Enter => self.editing = None,
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if editing == Editing::Search && !c.is_ascii_digit() => {}
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+        if self.editing == Some(Editing::Search) {
+            self.error = None;
+            match search_id(&self.rows, &self.input) {
+                Some(pos) => {
+                    self.cursor = pos;
+                    *selected = self.rows[pos];
+                }
+                None if !self.input.is_empty() => self.error = Some(format!("no household #{}…", self.input)),
+                None => {}
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 // src/ui/layout.rs
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 
@@ -1,3 +1,4 @@
 // src/ui/mod.rs
+pub mod browser;
 pub mod layout;
 pub mod render;
@@ -1,5 +1,9 @@
 // src/ui/render.rs
 use crate::app::{App, View};
+use crate::simulation::inspect::Activity;
+use crate::simulation::production::Bottleneck;
+use crate::simulation::query::SortKey;
+use crate::ui::browser::Editing;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     prelude::*,
@@ -18,6 +22,7 @@ pub fn render(f: &mut Frame, app: &mut App) {
         View::Overview => render_overview(f, chunks[1], app),
         View::Households => render_households(f, chunks[1], app),
         View::Goods => render_goods(f, chunks[1], app),
+        View::Production => render_production(f, chunks[1], app),
         View::Stocks => render_stocks(f, chunks[1], app),
     }
     render_footer(f, chunks[2], app);
@@ -28,23 +33,28 @@ fn tab_title(v: View) -> &'static str {
         View::Overview => "Overview",
         View::Households => "Households",
         View::Goods => "Goods",
+        View::Production => "Production",
         View::Stocks => "Stocks",
     }
 }
 
 fn render_header(f: &mut Frame, area: Rect, app: &App) {
-    let tabs = ["Overview", "Households", "Goods", "Stocks"];
+    let tabs = ["Overview", "Households", "Goods", "Production", "Stocks"];
     let idx = match app.view {
         View::Overview => 0,
         View::Households => 1,
         View::Goods => 2,
-        View::Stocks => 3,
+        View::Production => 3,
+        View::Stocks => 4,
     };
     let t = Tabs::new(tabs)
         .select(idx)
         .block(Block::default().borders(Borders::ALL).title(format!(
-            "EconomySim  | tick {} | households {} | paused {}",
-            app.derived.tick, app.derived.households, app.paused
+            "EconomySim  | {} (tick {}) | households {} | paused {}",
+            app.sim.structure.calendar.date(app.derived.tick),
+            app.derived.tick,
+            app.derived.households,
+            app.paused
         )))
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
@@ -52,24 +62,44 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
 }
 
 fn render_footer(f: &mut Frame, area: Rect, app: &App) {
-    let help = "q quit | p pause | . step | ←/→ tabs | ↑/↓ select | r reset | x force sell all stocks";
+    let help = match app.view {
+        View::Households if app.browser.editing.is_some() => "type to edit | Enter apply | Esc cancel",
+        View::Households => "↑/↓ PgUp/PgDn Home/End select | s sort column | S reverse | f filter | / search id | ←/→ tabs | p pause | . step | r reset | x force sell all stocks | l reload data | q quit",
+        _ => "q quit | p pause | . step | ←/→ tabs | ↑/↓ select | r reset | x force sell all stocks | l reload data",
+    };
     let msg = if app.last_action.is_empty() { help.to_string() } else { format!("{}  |  last: {}", help, app.last_action) };
     let p = Paragraph::new(msg).block(Block::default().borders(Borders::ALL));
     f.render_widget(p, area);
 }
 
 fn render_overview(f: &mut Frame, area: Rect, app: &App) {
-    let lines = vec![
+    let mut lines = vec![
         Line::from(Span::styled(tab_title(app.view), Style::default().add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from(format!("Total cash: {:.2}", app.derived.total_cash)),
         Line::from(format!("Average utility: {:.3}", app.derived.avg_utility)),
+        Line::from(format!("Cash Gini: {:.3}", app.derived.cash_gini)),
+        Line::from(format!("Average good price: {:.3}", app.derived.avg_good_price)),
+        Line::from(format!(
+            "Stock holdings: {:.2}  |  Margin debt: {:.2}",
+            app.derived.stock_market_value, app.derived.total_margin_debt
+        )),
+        Line::from(format!("Margin calls: {}", app.derived.margin_calls)),
+        Line::from(format!(
+            "Bankruptcies: {} households, {} firms",
+            app.derived.household_bankruptcies, app.derived.firm_bankruptcies
+        )),
         Line::from(""),
         Line::from(format!("Goods: {}", app.sim.structure.goods.len())),
         Line::from(format!("Needs: {}", app.sim.structure.needs.len())),
         Line::from(format!("Household types: {}", app.sim.structure.household_types.len())),
         Line::from(format!("Stocks: {}", app.sim.structure.stocks.len())),
+        Line::from(""),
+        Line::from(Span::styled("Recent events", Style::default().add_modifier(Modifier::BOLD))),
     ];
+    for ev in app.sim.events.events.iter().rev().take(8) {
+        lines.push(Line::from(format!("  [{}] {}", ev.tick, ev.describe(&app.sim.structure))));
+    }
     let p = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Overview")).wrap(Wrap { trim: true });
     f.render_widget(p, area);
 }
@@ -80,47 +110,120 @@ fn render_households(f: &mut Frame, area: Rect, app: &mut App) {
         .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
         .split(area);
 
-    let items: Vec<ListItem> = app
-        .sim
-        .households
+    app.browser.refresh(&app.sim, &mut app.selected_household);
+    let bar = app.browser.editing.is_some() || app.browser.error.is_some() || !app.browser.query.filter.is_empty();
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(if bar { 3 } else { 0 })])
+        .split(cols[0]);
+
+    // Borders and the header take three lines; only the rows on screen are formatted.
+    app.browser.scroll_to_cursor(parts[0].height.saturating_sub(3) as usize);
+    let b = &app.browser;
+    let s = &app.sim.structure;
+    let (goods_prices, stock_prices) = (&app.sim.market.price, &app.sim.stock_market.price);
+    let rows: Vec<Row> = b.rows[b.offset..(b.offset + b.page).min(b.rows.len())]
         .iter()
-        .map(|h| {
-            let kind = &app.sim.structure.household_types[h.kind.0 as usize].display_name;
-            ListItem::new(format!("#{} {:<10} cash={:>8.2} util={:>6.2}", h.id.0, kind, h.cash, h.utility))
+        .filter_map(|&id| app.sim.households.position(id))
+        .map(|i| {
+            let h = app.sim.households.get(i);
+            Row::new(vec![
+                format!("#{}", h.id.0),
+                s.household_types[h.kind.0 as usize].display_name.clone(),
+                format!("{:.2}", h.cash),
+                format!("{:.2}", h.utility),
+                format!("{:.2}", h.net_worth(goods_prices, stock_prices)),
+            ])
         })
         .collect();
 
-    let mut state = ListState::default();
-    if !items.is_empty() {
-        state.select(Some(app.selected_household.min(items.len() - 1)));
+    let arrow = if b.query.descending { " ▼" } else { " ▲" };
+    let header: Vec<String> = SortKey::ALL
+        .iter()
+        .zip(["Id", "Type", "Cash", "Utility", "Net worth"])
+        .map(|(&k, name)| if k == b.query.sort { format!("{}{}", name, arrow) } else { name.to_string() })
+        .collect();
+    let title = format!("Households {} of {} | sort {}", b.rows.len(), app.sim.households.len(), b.query.sort.as_str());
+
+    let mut state = TableState::default();
+    if !rows.is_empty() {
+        state.select(Some(b.cursor - b.offset));
     }
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(14),
+            Constraint::Percentage(22),
+            Constraint::Percentage(22),
+            Constraint::Percentage(18),
+            Constraint::Percentage(24),
+        ],
+    )
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .column_spacing(1);
+    f.render_stateful_widget(table, parts[0], &mut state);
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Households"))
-        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-    f.render_stateful_widget(list, cols[0], &mut state);
+    if bar {
+        let (label, text) = match b.editing {
+            Some(Editing::Filter) => ("Filter", format!("{}▏", b.input)),
+            Some(Editing::Search) => ("Search id", format!("{}▏", b.input)),
+            None => ("Filter", b.query.filter.text.clone()),
+        };
+        let mut spans = vec![Span::raw(text)];
+        if let Some(err) = &b.error {
+            spans.push(Span::styled(format!("  {}", err), Style::default().fg(Color::Red)));
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)).block(Block::default().borders(Borders::ALL).title(label)), parts[1]);
+    }
 
     render_household_detail(f, cols[1], app);
 }
 
 fn render_household_detail(f: &mut Frame, area: Rect, app: &App) {
-    if app.sim.households.is_empty() {
+    let Some(idx) = app.sim.households.position(app.selected_household) else {
         f.render_widget(Paragraph::new("No households").block(Block::default().borders(Borders::ALL)), area);
         return;
-    }
+    };
+    let h = app.sim.households.get(idx);
+    let s = &app.sim.structure;
+    let td = &s.household_types[h.kind.0 as usize];
+    let kind = &td.display_name;
+    let insp = app.sim.inspector.as_ref().filter(|i| i.household == h.id);
 
-    let idx = app.selected_household.min(app.sim.households.len() - 1);
-    let h = &app.sim.households[idx];
-    let kind = &app.sim.structure.household_types[h.kind.0 as usize].display_name;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Length(4), Constraint::Min(5)])
+        .split(area);
+    render_sparkline(f, rows[0], "Cash", insp.map(|i| &i.cash), Color::Green);
+    render_sparkline(f, rows[1], "Utility", insp.map(|i| &i.utility), Color::Cyan);
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(rows[2]);
 
     let mut lines = Vec::new();
     lines.push(Line::from(format!("Household #{}  |  Type: {}", h.id.0, kind)));
     lines.push(Line::from(format!("Cash: {:.2}  |  Utility: {:.3}", h.cash, h.utility)));
+    lines.push(Line::from(format!(
+        "Margin debt: {:.2}  |  Equity: {:.2}",
+        h.margin_debt,
+        h.equity(&app.sim.stock_market.price)
+    )));
     lines.push(Line::from(""));
 
     lines.push(Line::from(Span::styled("Inventory", Style::default().add_modifier(Modifier::BOLD))));
+    if let Some(st) = &app.sim.structure.household_types[h.kind.0 as usize].storage {
+        lines.push(Line::from(format!(
+            "  Storage {:.1} / {:.1} ({} overflow)",
+            h.volume_used(&app.sim.structure),
+            st.capacity,
+            st.overflow.as_str()
+        )));
+    }
     for gd in &app.sim.structure.goods {
-        let q = h.inventory.get(gd.id);
+        let q = h.good(gd.id);
         if q.abs() > 1e-9 {
             lines.push(Line::from(format!("  {:<18} {:>10.2}", gd.display_name, q)));
         }
@@ -129,22 +232,75 @@ fn render_household_detail(f: &mut Frame, area: Rect, app: &App) {
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled("Portfolio", Style::default().add_modifier(Modifier::BOLD))));
     for st in &app.sim.structure.stocks {
-        let q = h.portfolio.get(st.id);
+        let q = h.shares(st.id);
         if q.abs() > 1e-9 {
             lines.push(Line::from(format!("  {:<18} {:>10.4}", st.display_name, q)));
         }
     }
 
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Needs", Style::default().add_modifier(Modifier::BOLD))));
+    for (slot, &nid) in td.needs.iter().enumerate() {
+        let nd = &s.needs[nid.0 as usize];
+        let met = h.fulfilled_last[slot];
+        let streak = insp.and_then(|i| i.streaks.get(slot)).map_or_else(|| "-".to_string(), |n| n.to_string());
+        let text = format!(
+            "  {:<14} {:<10} due in {:>3}  {:<5} streak {}",
+            s.need_ids.vec[nid.0 as usize],
+            s.good_name(nd.good),
+            h.next_due_in[slot],
+            if met { "met" } else { "unmet" },
+            streak
+        );
+        lines.push(if met { Line::from(text) } else { Line::from(Span::styled(text, Style::default().fg(Color::Red))) });
+    }
+
     let p = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Details")).wrap(Wrap { trim: true });
-    f.render_widget(p, area);
+    f.render_widget(p, cols[0]);
+
+    let height = cols[1].height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = insp
+        .into_iter()
+        .flat_map(|i| i.timeline.iter().rev().take(height))
+        .map(|(tick, a)| {
+            let color = match a {
+                Activity::MissedNeed { .. } => Color::Red,
+                Activity::Traded { .. } => Color::Yellow,
+                Activity::Transfer { .. } | Activity::Event(_) | Activity::Removed => Color::Magenta,
+                Activity::Bought { .. } | Activity::Consumed { .. } => Color::Reset,
+            };
+            ListItem::new(format!("[{}] {}", tick, a.describe(s))).style(Style::default().fg(color))
+        })
+        .collect();
+    f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title("Timeline")), cols[1]);
+}
+
+/// The most recent values of `series` that fit, scaled between their minimum and maximum.
+fn render_sparkline(f: &mut Frame, area: Rect, label: &str, series: Option<&std::collections::VecDeque<f64>>, color: Color) {
+    let width = area.width.saturating_sub(2) as usize;
+    let values: Vec<f64> = series.map_or_else(Vec::new, |v| v.iter().skip(v.len().saturating_sub(width)).copied().collect());
+    let (lo, hi) = values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let data: Vec<u64> = values.iter().map(|&v| if hi > lo { 1 + ((v - lo) / (hi - lo) * 99.0) as u64 } else { 50 }).collect();
+    let title = match values.last() {
+        Some(last) => format!("{} {:.2}  (range {:.2} .. {:.2})", label, last, lo, hi),
+        None => label.to_string(),
+    };
+    let spark = Sparkline::default().block(Block::default().borders(Borders::ALL).title(title)).data(&data).max(100).style(Style::default().fg(color));
+    f.render_widget(spark, area);
 }
 
 fn render_goods(f: &mut Frame, area: Rect, app: &App) {
+    let s = &app.sim.structure;
     let mut rows = Vec::new();
-    for gd in &app.sim.structure.goods {
+    for gd in &s.goods {
         let i = gd.id.0 as usize;
+        let kind = match gd.quality {
+            Some(q) => format!("{} ({})", gd.category.as_str(), s.quality_tiers[q.0 as usize].display_name),
+            None => gd.category.as_str().to_string(),
+        };
         rows.push(Row::new(vec![
             gd.display_name.clone(),
+            kind,
             format!("{:.3}", app.sim.market.price[i]),
             format!("{:.2}", app.sim.market.demand[i]),
             format!("{:.2}", app.sim.market.supply[i]),
@@ -154,43 +310,207 @@ fn render_goods(f: &mut Frame, area: Rect, app: &App) {
     let table = Table::new(
         rows,
         [
-            Constraint::Percentage(45),
-            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(17),
+            Constraint::Percentage(16),
             Constraint::Percentage(17),
-            Constraint::Percentage(18),
         ],
     )
-        .header(Row::new(vec!["Good", "Price", "Buy", "Sell"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .header(Row::new(vec!["Good", "Kind", "Price", "Buy", "Sell"]).style(Style::default().add_modifier(Modifier::BOLD)))
         .block(Block::default().borders(Borders::ALL).title("Goods Market"))
         .column_spacing(1);
 
     f.render_widget(table, area);
 }
 
+/// Rules in supply chain order with how close each came to capacity and why not.
+fn render_production(f: &mut Frame, area: Rect, app: &App) {
+    let s = &app.sim.structure;
+    let p = &app.sim.production;
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(4), Constraint::Min(3)]).split(area);
+
+    let stock: Vec<String> = s
+        .goods
+        .iter()
+        .filter(|g| s.intermediate[g.id.0 as usize])
+        .map(|g| format!("{} {:.1}", g.display_name, p.stock[g.id.0 as usize]))
+        .collect();
+    let shocks: Vec<&str> = s.shocks.iter().filter(|sh| p.is_active(sh.id)).map(|sh| sh.display_name.as_str()).collect();
+    let mut lines = vec![
+        Line::from(format!("Labor: {:.1} of {:.1} workers employed, wages {:.2}/tick", p.labor_used, p.labor_supply, p.wages)),
+        Line::from(format!("Intermediate stock: {}", if stock.is_empty() { "-".to_string() } else { stock.join(", ") })),
+    ];
+    if !shocks.is_empty() {
+        lines.push(Line::from(Span::styled(format!("Shocks: {}", shocks.join(", ")), Style::default().fg(Color::Red))));
+    }
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::LEFT | Borders::RIGHT | Borders::TOP).title("Supply Chain")), chunks[0]);
+
+    let mut rows = Vec::new();
+    for &rid in &s.rule_order {
+        let r = &s.production_rules[rid.0 as usize];
+        let i = rid.0 as usize;
+        let util = p.utilization[i];
+        let limit = match p.bottleneck[i] {
+            Bottleneck::None => "-".to_string(),
+            Bottleneck::Labor => "labor".to_string(),
+            Bottleneck::Capital(g) => format!("capital: {}", s.good_name(g)),
+            Bottleneck::Input(g) => format!("input: {}", s.good_name(g)),
+        };
+        let style = if util < 0.5 { Style::default().fg(Color::Red) } else if util < 0.95 { Style::default().fg(Color::Yellow) } else { Style::default() };
+        rows.push(
+            Row::new(vec![
+                r.display_name.clone(),
+                format!("{:.1}", r.capacity),
+                format!("{:>5.1}%", util * 100.0),
+                format!("{:.2}", p.last_multiplier[i]),
+                limit,
+            ])
+            .style(style),
+        );
+    }
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(28),
+            Constraint::Percentage(12),
+            Constraint::Percentage(15),
+            Constraint::Percentage(12),
+            Constraint::Percentage(33),
+        ],
+    )
+        .header(Row::new(vec!["Rule", "Capacity", "Utilization", "Yield", "Bottleneck"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL))
+        .column_spacing(1);
+
+    f.render_widget(table, chunks[1]);
+}
+
 fn render_stocks(f: &mut Frame, area: Rect, app: &App) {
+    let s = &app.sim.structure;
+    let has_indices = !s.indices.is_empty();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(if has_indices {
+            vec![Constraint::Length(3), Constraint::Min(5), Constraint::Length(12)]
+        } else {
+            vec![Constraint::Length(0), Constraint::Min(5), Constraint::Length(0)]
+        })
+        .split(area);
+
+    if has_indices {
+        render_index_header(f, chunks[0], app);
+        render_index_chart(f, chunks[2], app);
+    }
+
     let mut rows = Vec::new();
-    for st in &app.sim.structure.stocks {
+    for st in &s.stocks {
         let i = st.id.0 as usize;
+        let sm = &app.sim.stock_market;
+        let price = if sm.listed[i] { format!("{:.3}", sm.price[i]) } else { "unlisted".to_string() };
         rows.push(Row::new(vec![
             st.display_name.clone(),
-            format!("{:.3}", app.sim.stock_market.price[i]),
-            format!("{:.2}", app.sim.stock_market.demand[i]),
-            format!("{:.2}", app.sim.stock_market.supply[i]),
+            st.sector.map_or("-", |sec| s.sector_name(sec)).to_string(),
+            price,
+            format!("{:.3}", sm.earnings[i]),
+            format!("{:.2}%", sm.dividend_yield(st.id) * 100.0),
+            sm.pe_ratio(st.id).map_or_else(|| "-".to_string(), |pe| format!("{:.1}", pe)),
+            format!("{:.0}", sm.outstanding[i]),
+            format!("{:.0}", sm.available(st.id)),
+            format!("{:.2}", sm.demand[i]),
+            format!("{:.2}", sm.supply[i]),
         ]));
     }
 
     let table = Table::new(
         rows,
         [
-            Constraint::Percentage(45),
-            Constraint::Percentage(20),
-            Constraint::Percentage(17),
-            Constraint::Percentage(18),
+            Constraint::Percentage(16),
+            Constraint::Percentage(11),
+            Constraint::Percentage(9),
+            Constraint::Percentage(8),
+            Constraint::Percentage(8),
+            Constraint::Percentage(7),
+            Constraint::Percentage(11),
+            Constraint::Percentage(11),
+            Constraint::Percentage(9),
+            Constraint::Percentage(10),
         ],
     )
-        .header(Row::new(vec!["Stock", "Price", "Buy", "Sell"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .header(
+            Row::new(vec!["Stock", "Sector", "Price", "EPS", "Yield", "P/E", "Shares", "Float", "Buy", "Sell"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
         .block(Block::default().borders(Borders::ALL).title("Stock Market"))
         .column_spacing(1);
 
-    f.render_widget(table, area);
+    f.render_widget(table, chunks[1]);
+}
+
+fn render_index_header(f: &mut Frame, area: Rect, app: &App) {
+    let mut spans = Vec::new();
+    for ix in &app.sim.structure.indices {
+        let change = app.sim.indices.change(ix.id) * 100.0;
+        let color = if change >= 0.0 { Color::Green } else { Color::Red };
+        if !spans.is_empty() {
+            spans.push(Span::raw("  |  "));
+        }
+        spans.push(Span::styled(ix.display_name.clone(), Style::default().add_modifier(Modifier::BOLD)));
+        spans.push(Span::raw(format!(" {:.2} ", app.sim.indices.value[ix.id.0 as usize])));
+        spans.push(Span::styled(format!("({:+.2}%)", change), Style::default().fg(color)));
+    }
+    let p = Paragraph::new(Line::from(spans)).block(Block::default().borders(Borders::ALL).title("Indices"));
+    f.render_widget(p, area);
+}
+
+fn render_index_chart(f: &mut Frame, area: Rect, app: &App) {
+    const COLORS: [Color; 6] = [Color::Cyan, Color::Yellow, Color::Magenta, Color::Green, Color::Blue, Color::Red];
+
+    let points: Vec<Vec<(f64, f64)>> = app
+        .sim
+        .indices
+        .history
+        .iter()
+        .map(|h| h.iter().enumerate().map(|(x, &y)| (x as f64, y)).collect())
+        .collect();
+
+    let (mut lo, mut hi, mut len) = (f64::MAX, f64::MIN, 1.0f64);
+    for pts in &points {
+        for &(x, y) in pts {
+            lo = lo.min(y);
+            hi = hi.max(y);
+            len = len.max(x);
+        }
+    }
+    if lo > hi {
+        (lo, hi) = (0.0, 1.0);
+    }
+
+    let datasets: Vec<Dataset> = app
+        .sim
+        .structure
+        .indices
+        .iter()
+        .zip(&points)
+        .enumerate()
+        .map(|(i, (ix, pts))| {
+            Dataset::default()
+                .name(ix.display_name.clone())
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(COLORS[i % COLORS.len()]))
+                .data(pts)
+        })
+        .collect();
+
+    let chart = Chart::new(datasets)
+        .block(Block::default().borders(Borders::ALL).title("Index history"))
+        .x_axis(Axis::default().bounds([0.0, len]))
+        .y_axis(
+            Axis::default()
+                .bounds([lo, hi])
+                .labels(vec![Span::raw(format!("{:.0}", lo)), Span::raw(format!("{:.0}", hi))]),
+        );
+    f.render_widget(chart, area);
 }
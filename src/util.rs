@@ -17,7 +17,7 @@ impl Ticker {
     pub fn should_tick(&mut self) -> bool {
         let now = Instant::now();
         if now >= self.next {
-            self.next = self.next + self.period;
+            self.next += self.period;
             true
         } else {
             false
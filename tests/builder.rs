@@ -0,0 +1,89 @@
+//! `StructureBuilder` must produce the same economy as the equivalent XML.
+
+mod common;
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::{CorporateActionKind, IndexWeighting, InsolvencyResolution};
+use economy_sim::simulation::engine::Simulation;
+
+/// `tests/fixtures/basic`, written in code.
+fn basic() -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0).display_name("Bread").decay_rate(0.1);
+    b.good("water", 1.0).display_name("Water");
+    b.need("need_bread", "bread", 1.0, 4).priority(1);
+    b.need("need_water", "water", 2.0, 2);
+
+    b.insolvency(20, InsolvencyResolution::Reset, Some("poor"), 1.0);
+    b.household_type("trader", 800.0)
+        .display_name("Trader")
+        .inventory("bread", 4.0)
+        .inventory("water", 8.0)
+        .shares("steady", 5.0)
+        .shares("bust", 5.0)
+        .need("need_bread")
+        .need("need_water")
+        .income_per_tick(1.0)
+        .max_leverage(2.0)
+        .short_probability(0.3);
+    b.household_type("indexer", 600.0).display_name("Indexer").need("need_water").income_per_tick(0.5).index_fund("all");
+    b.household_type("poor", 5.0).display_name("Poor").need("need_bread").need("need_water").income_per_tick(0.1);
+    b.household_type("hoarder", 0.0).display_name("Hoarder").inventory("bread", 10.0);
+
+    b.rule("bake", 5).display_name("Bake").input("water", 1.0).output("bread", 1.0);
+
+    b.margin(0.5, 0.25, 0.001, 0.001);
+    b.firm_insolvency(2, 0.2);
+    b.stock("steady", 50.0, 10_000)
+        .display_name("Steady")
+        .volatility(0.03)
+        .earnings(2.0, 0.0, 0.1)
+        .dividends(0.5, 10)
+        .corporate_action(CorporateActionKind::Split, 60, 3.0)
+        .corporate_action(CorporateActionKind::Buyback, 90, 2000.0);
+    b.stock("bust", 20.0, 10_000).display_name("Bust").volatility(0.05).earnings(-1.0, 0.0, 0.0).dividends(0.5, 25);
+    b.index("all", IndexWeighting::Cap).display_name("All");
+    b
+}
+
+#[test]
+fn builder_matches_xml_fixture() {
+    let built = basic().build().unwrap();
+    let loaded = common::fixture("basic");
+    assert_eq!(built.good_ids.vec, loaded.good_ids.vec);
+    assert_eq!(built.household_type_ids.vec, loaded.household_type_ids.vec);
+
+    let mut a = Simulation::new(built, 5, 100);
+    let mut b = Simulation::new(loaded, 5, 100);
+    for _ in 0..200 {
+        a.tick();
+        b.tick();
+        assert_eq!(a.state_hash(), b.state_hash(), "diverged at tick {}", a.tick);
+    }
+}
+
+#[test]
+fn unknown_references_are_rejected() {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0);
+    b.need("eat", "cake", 1.0, 4);
+    let err = b.build().unwrap_err().to_string();
+    assert!(err.contains("cake"), "{}", err);
+
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0);
+    b.household_type("worker", 100.0).need("sleep");
+    assert!(b.build().unwrap_err().to_string().contains("sleep"));
+}
+
+#[test]
+fn builder_extends_loaded_data() {
+    let mut b = StructureBuilder::from_raw(common::raw_fixture("basic"));
+    b.good("salt", 0.5);
+    b.need("need_salt", "salt", 0.1, 10);
+    b.household_type("cook", 100.0).need("need_salt").need("need_bread");
+    let s = b.build().unwrap();
+    assert_eq!(s.goods.len(), 3);
+    let cook = &s.household_types[s.household_type_ids.map["cook"] as usize];
+    assert_eq!(cook.needs.len(), 2);
+}
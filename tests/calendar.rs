@@ -0,0 +1,65 @@
+//! Calendar durations in data files and simulated dates.
+
+mod common;
+
+use economy_sim::data::calendar::Calendar;
+use economy_sim::data::compiled::{CorporateActionKind, Structure};
+use economy_sim::data::formats::Format;
+use economy_sim::data::xml::NeedsXml;
+
+#[test]
+fn durations_convert_with_the_calendar() {
+    let cal = Calendar { ticks_per_hour: 4, ..Calendar::default() };
+    assert_eq!(cal.parse_duration("20").unwrap(), 20);
+    assert_eq!(cal.parse_duration("20t").unwrap(), 20);
+    assert_eq!(cal.parse_duration("12h").unwrap(), 48);
+    assert_eq!(cal.parse_duration("1d").unwrap(), 96);
+    assert_eq!(cal.parse_duration("1d12h").unwrap(), 144);
+    assert_eq!(cal.parse_duration("1w").unwrap(), 7 * 96);
+    assert_eq!(cal.parse_duration("1mo").unwrap(), 30 * 96);
+    assert_eq!(cal.parse_duration("1y").unwrap(), 360 * 96);
+    assert_eq!(cal.parse_duration("0.5h").unwrap(), 2);
+    for bad in ["", "d", "3q", "-1d", "1m"] {
+        assert!(cal.parse_duration(bad).is_err(), "{:?} should not parse", bad);
+    }
+}
+
+#[test]
+fn dates_roll_over_days_months_and_years() {
+    let cal = Calendar { ticks_per_hour: 2, ..Calendar::default() };
+    assert_eq!(cal.date(0).to_string(), "Y1 M01 D01 00:00");
+    assert_eq!(cal.date(3).to_string(), "Y1 M01 D01 01:30");
+    assert_eq!(cal.date(cal.ticks_per_day() * 31).to_string(), "Y1 M02 D02 00:00");
+    assert_eq!(cal.date(cal.ticks_per_year() + 5).to_string(), "Y2 M01 D01 02:30");
+    assert_eq!(cal.month_of_year(cal.ticks_per_month() * 13), 1);
+}
+
+#[test]
+fn data_files_accept_durations() {
+    let mut cfg = common::fixture_config("basic");
+    cfg.calendar = Calendar { ticks_per_hour: 3, ..Calendar::default() };
+    let mut raw = economy_sim::data::xml::load_all(&cfg).unwrap();
+    raw.needs.needs[0].interval_ticks = "1d".into();
+    raw.production.rules[0].ticks = "2h".into();
+    raw.stocks.stocks[0].corporate_actions.actions[0].tick = "1w".into();
+    let s = Structure::compile(raw).unwrap();
+    assert_eq!(s.needs[0].interval_ticks, 72);
+    assert_eq!(s.production_rules[0].ticks, 6);
+    let split = s.stocks[0].corporate_actions.iter().find(|a| a.kind == CorporateActionKind::Split).unwrap();
+    assert_eq!(split.tick, 7 * 72);
+    assert_eq!(s.calendar.ticks_per_hour, 3);
+
+    let mut raw = common::raw_fixture("basic");
+    raw.needs.needs[1].interval_ticks = "2fortnights".into();
+    let err = format!("{:#}", Structure::compile(raw).unwrap_err());
+    assert!(err.contains("need need_water interval_ticks") && err.contains("unknown time unit"), "{}", err);
+}
+
+#[test]
+fn durations_parse_from_text_and_numbers() {
+    let needs = r#"<needs><need id="n"><good_ref>g</good_ref><amount>1</amount><interval_ticks>1d</interval_ticks><priority>0</priority></need></needs>"#;
+    let parsed: NeedsXml = Format::Xml.parse(needs).unwrap();
+    assert_eq!(parsed.needs[0].interval_ticks.0, "1d");
+    let json: NeedsXml = Format::Json.parse(r#"{"need": [{"id": "n", "good_ref": "g", "amount": 1, "interval_ticks": 12, "priority": 0}]}"#).unwrap();
+    assert_eq!(json.needs[0].interval_ticks.0, "12");
+}
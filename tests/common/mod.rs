@@ -0,0 +1,71 @@
+#![allow(dead_code)]
+
+use economy_sim::config::{Config, DataPaths};
+use economy_sim::data::compiled::Structure;
+use economy_sim::data::xml::{self, RawXml};
+use economy_sim::simulation::engine::Simulation;
+
+pub fn fixture_config(name: &str) -> Config {
+    let path = |file: &str| format!("tests/fixtures/{}/{}.xml", name, file);
+    Config {
+        data_paths: DataPaths {
+            goods: path("goods"),
+            needs: path("needs"),
+            households: path("households"),
+            production: path("production"),
+            stocks: path("stocks"),
+        },
+        ..Config::default()
+    }
+}
+
+pub fn raw_fixture(name: &str) -> RawXml {
+    xml::load_all(&fixture_config(name)).expect("fixture parses")
+}
+
+pub fn fixture(name: &str) -> Structure {
+    Structure::compile(raw_fixture(name)).expect("fixture compiles")
+}
+
+pub fn total_cash(sim: &Simulation) -> f64 {
+    sim.households.cash.iter().sum()
+}
+
+/// Runs one tick and checks that household cash moved exactly by the recorded flows.
+pub fn tick_conserving_cash(sim: &mut Simulation) {
+    let before = total_cash(sim);
+    sim.tick();
+    let after = total_cash(sim);
+    let flows = sim.cash_flows;
+    let scale = 1.0 + before.abs() + after.abs();
+    assert!(
+        (after - before - flows.total()).abs() <= 1e-9 * scale,
+        "tick {}: cash moved by {} but flows sum to {} ({:?})",
+        sim.tick,
+        after - before,
+        flows.total(),
+        flows
+    );
+}
+
+/// State invariants that hold after every tick for any economy.
+pub fn check_invariants(sim: &Simulation) {
+    for h in sim.households.iter() {
+        assert!(h.cash.is_finite() && h.margin_debt.is_finite(), "household {} has non-finite balances", h.id.0);
+        assert!(h.margin_debt >= 0.0, "household {} has negative margin debt", h.id.0);
+        for (g, &q) in h.inventory.iter().enumerate() {
+            assert!(q >= 0.0 && q.is_finite(), "household {} holds {} of good {}", h.id.0, q, g);
+        }
+        let td = &sim.structure.household_types[h.kind.0 as usize];
+        for (slot, &nid) in td.needs.iter().enumerate() {
+            let interval = sim.structure.needs[nid.0 as usize].interval_ticks;
+            assert!(h.next_due_in[slot] <= interval, "household {} need timer above its interval", h.id.0);
+        }
+    }
+    for (i, &p) in sim.market.price.iter().enumerate() {
+        assert!(p >= 0.01 && p.is_finite(), "good {} priced at {}", i, p);
+    }
+    for (i, &p) in sim.stock_market.price.iter().enumerate() {
+        assert!(p >= 0.01 && p.is_finite(), "stock {} priced at {}", i, p);
+    }
+}
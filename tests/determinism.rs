@@ -0,0 +1,65 @@
+//! Runs the binary headlessly and compares per-tick state hash trails against
+//! the committed golden files. After an intentional behaviour change, refresh
+//! them with `BLESS=1 cargo test --test determinism`.
+
+use std::path::Path;
+use std::process::Command;
+
+const CONFIG: &str = "tests/fixtures/golden/config.toml";
+
+fn trail(seed: u64, ticks: u64) -> String {
+    trail_with(&["--seed", &seed.to_string(), "--ticks", &ticks.to_string()])
+}
+
+fn trail_with(args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_EconomySim"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--config", CONFIG, "hash"])
+        .args(args)
+        .output()
+        .expect("running EconomySim hash");
+    assert!(out.status.success(), "hash command failed: {}", String::from_utf8_lossy(&out.stderr));
+    String::from_utf8(out.stdout).expect("utf-8 trail")
+}
+
+fn check_golden(name: &str, seed: u64, ticks: u64) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+    let actual = trail(seed, ticks);
+    if std::env::var_os("BLESS").is_some() {
+        std::fs::write(&path, &actual).expect("writing golden trail");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e));
+    for (n, (a, e)) in actual.lines().zip(expected.lines()).enumerate() {
+        assert_eq!(a, e, "{} diverges from the golden trail at line {}", name, n + 1);
+    }
+    assert_eq!(actual.lines().count(), expected.lines().count(), "{} has a different length", name);
+}
+
+#[test]
+fn golden_seed_1337() {
+    check_golden("seed1337.trail", 1337, 300);
+}
+
+#[test]
+fn golden_seed_42() {
+    check_golden("seed42.trail", 42, 300);
+}
+
+#[test]
+fn same_seed_same_trail() {
+    assert_eq!(trail(7, 100), trail(7, 100));
+}
+
+#[test]
+fn different_seed_different_trail() {
+    assert_ne!(trail(7, 20), trail(8, 20));
+}
+
+#[test]
+fn thread_count_does_not_change_trail() {
+    // Enough households to span several parallel chunks.
+    let run = |threads: &str| trail_with(&["--ticks", "30", "--households", "9000", "--threads", threads]);
+    assert_eq!(run("1"), run("4"));
+}
@@ -0,0 +1,79 @@
+//! TOML, JSON and RON data sets must load into the same economy as the XML they were converted from.
+
+mod common;
+
+use economy_sim::config::{Config, DataPaths};
+use economy_sim::data::compiled::Structure;
+use economy_sim::data::formats::{self, Format};
+use economy_sim::data::xml;
+use economy_sim::simulation::engine::Simulation;
+use std::path::{Path, PathBuf};
+
+fn config_for(dir: &Path, ext: &str) -> Config {
+    let path = |file: &str| dir.join(format!("{}.{}", file, ext)).display().to_string();
+    Config {
+        data_paths: DataPaths {
+            goods: path("goods"),
+            needs: path("needs"),
+            households: path("households"),
+            production: path("production"),
+            stocks: path("stocks"),
+        },
+        ..Config::default()
+    }
+}
+
+/// Tests run in parallel, so each converts into its own directory.
+fn converted(test: &str, format: Format) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("formats").join(test).join(format.extension());
+    formats::write_data_set(&common::raw_fixture("basic"), format, &dir).unwrap();
+    dir
+}
+
+fn hash_after(structure: Structure, ticks: u64) -> u64 {
+    let mut sim = Simulation::new(structure, 11, 80);
+    for _ in 0..ticks {
+        sim.tick();
+    }
+    sim.state_hash()
+}
+
+#[test]
+fn converted_data_sets_simulate_identically() {
+    let expected = hash_after(common::fixture("basic"), 200);
+    for format in [Format::Toml, Format::Json, Format::Ron] {
+        let dir = converted("identical", format);
+        let raw = xml::load_all(&config_for(&dir, format.extension())).unwrap_or_else(|e| panic!("{}: {:#}", format, e));
+        assert_eq!(hash_after(Structure::compile(raw).unwrap(), 200), expected, "{} data set diverges", format);
+    }
+}
+
+#[test]
+fn formats_can_be_mixed_per_file() {
+    let dir = converted("mixed", Format::Json);
+    let mut cfg = common::fixture_config("basic");
+    cfg.data_paths.goods = dir.join("goods.json").display().to_string();
+    let s = Structure::compile(xml::load_all(&cfg).unwrap()).unwrap();
+    assert_eq!(hash_after(s, 50), hash_after(common::fixture("basic"), 50));
+}
+
+#[test]
+fn hand_written_json_uses_plain_keys() {
+    let goods: xml::GoodsXml = Format::Json
+        .parse(r#"{"good": [{"id": "salt", "display_name": "Salt", "base_price": 0.5, "decay_rate": 0.0, "stackable": true}]}"#)
+        .unwrap();
+    assert_eq!(goods.goods[0].id, "salt");
+}
+
+#[test]
+fn unknown_extensions_and_mods_over_non_xml_files_are_rejected() {
+    let mut cfg = common::fixture_config("basic");
+    cfg.data_paths.goods = "tests/fixtures/basic/goods.yaml".to_string();
+    assert!(format!("{:#}", xml::load_all(&cfg).unwrap_err()).contains("unknown data format"));
+
+    let dir = converted("rejected", Format::Toml);
+    let mut cfg = config_for(&dir, "toml");
+    cfg.mods = vec!["tests/fixtures/mods/salt".to_string()];
+    let err = format!("{:#}", xml::load_all(&cfg).unwrap_err());
+    assert!(err.contains("is not XML"), "{}", err);
+}
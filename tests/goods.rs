@@ -0,0 +1,95 @@
+//! Good categories, quality tiers and durable goods.
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::{GoodCategory, Structure};
+use economy_sim::data::formats::Format;
+use economy_sim::data::ids::GoodId;
+use economy_sim::data::xml::GoodsXml;
+use economy_sim::simulation::engine::Simulation;
+
+/// One household that needs a fridge every tick and holds bread that rots.
+fn kitchen(fridge_held: f64) -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.quality_tier("premium", "Premium", 1.5);
+    b.good("bread", 5.0).category(GoodCategory::Food).decay_rate(0.1);
+    b.good("fridge", 100.0).category(GoodCategory::Durable).quality("premium").decay_rate(0.01).service("bread", 0.5);
+    b.need("need_fridge", "fridge", 1.0, 0);
+    b.household_type("cook", 1000.0).inventory("bread", 10.0).inventory("fridge", fridge_held).need("need_fridge");
+    b
+}
+
+#[test]
+fn goods_xml_reads_categories_tiers_and_services() {
+    let text = r#"<goods>
+        <quality_tier id="fine"><display_name>Fine</display_name><utility>2.0</utility></quality_tier>
+        <good id="bread"><display_name>Bread</display_name><category>food</category><quality>fine</quality>
+            <base_price>5</base_price><decay_rate>0.1</decay_rate><stackable>true</stackable></good>
+        <good id="water"><display_name>Water</display_name>
+            <base_price>1</base_price><decay_rate>0</decay_rate><stackable>true</stackable></good>
+        <good id="fridge"><display_name>Fridge</display_name><category>durable</category>
+            <base_price>100</base_price><decay_rate>0.01</decay_rate><stackable>false</stackable>
+            <services><service good_ref="bread" decay_multiplier="0.5" /></services></good>
+    </goods>"#;
+    let goods: GoodsXml = Format::Xml.parse(text).unwrap();
+    let mut raw = StructureBuilder::new().into_raw();
+    raw.goods = goods;
+    let s = Structure::compile(raw).unwrap();
+
+    let [bread, water, fridge] = [0, 1, 2].map(|i| &s.goods[i]);
+    assert_eq!((bread.category, bread.utility), (GoodCategory::Food, 2.0));
+    assert_eq!((water.category, water.utility, water.quality), (GoodCategory::Utility, 1.0, None));
+    assert_eq!(fridge.category, GoodCategory::Durable);
+    assert!(!fridge.category.is_consumed());
+    assert_eq!(bread.serviced_by, [(fridge.id, 0.5)]);
+}
+
+#[test]
+fn invalid_goods_are_rejected() {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0).category(GoodCategory::Food).service("bread", 0.5);
+    assert!(b.build().unwrap_err().to_string().contains("good bread has services but is not durable"));
+
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0).quality("gold");
+    assert!(b.build().unwrap_err().to_string().contains("good bread references unknown quality tier gold"));
+
+    let mut b = StructureBuilder::new();
+    b.good("fridge", 5.0).category(GoodCategory::Durable).service("ice", 0.5);
+    assert!(b.build().unwrap_err().to_string().contains("good fridge services unknown good ice"));
+
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0);
+    let mut raw = b.into_raw();
+    raw.goods.goods[0].category = "toy".to_string();
+    assert!(Structure::compile(raw).unwrap_err().to_string().contains("good bread has unknown category toy"));
+}
+
+#[test]
+fn durables_are_used_not_consumed() {
+    let mut sim = Simulation::new(kitchen(1.0).build().unwrap(), 1, 1);
+    sim.tick();
+    let cash = sim.households.cash[0];
+    sim.tick();
+    let h = sim.households.get(0);
+    // The need comes due on the second tick, after one more tick of depreciation:
+    // premium utility scaled by the fridge's condition.
+    assert!((h.utility - 1.5 * 0.99 * 0.99).abs() < 1e-12, "utility {}", h.utility);
+    assert!(h.last_consumed.is_empty());
+    // The worn fridge is topped up, not replaced.
+    assert!((h.good(GoodId(1)) - 1.0).abs() < 1e-12);
+    let upkeep = cash - h.cash;
+    assert!((upkeep - 100.0 * (1.0 - 0.99 * 0.99)).abs() < 1e-9, "paid {} for upkeep", upkeep);
+}
+
+#[test]
+fn services_slow_decay() {
+    let bread_after = |fridge: f64| {
+        let mut sim = Simulation::new(kitchen(fridge).build().unwrap(), 1, 1);
+        sim.households.get_mut(0).apply_decay(&sim.structure);
+        sim.households.get(0).inventory[0]
+    };
+    assert!((bread_after(0.0) - 9.0).abs() < 1e-12);
+    assert!((bread_after(1.0) - 9.5).abs() < 1e-12);
+    assert!((bread_after(0.5) - 9.25).abs() < 1e-12);
+    assert!((bread_after(3.0) - 9.5).abs() < 1e-12);
+}
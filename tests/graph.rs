@@ -0,0 +1,42 @@
+//! Dependency graph export.
+
+mod common;
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::graph;
+
+#[test]
+fn basic_fixture_graph() {
+    let s = common::fixture("basic");
+    let dot = graph::to_dot(&s);
+    // bread = g0, water = g1; bake turns water into bread.
+    assert!(dot.contains("g1 -> g0 [label=\"Bake\"]"), "{}", dot);
+    assert!(dot.contains("h0 -> g0 [label=\"need_bread\", style=dashed]"), "{}", dot);
+    assert!(dot.contains("g1 [label=\"Water\", shape=ellipse, color=red"), "{}", dot);
+    assert!(dot.ends_with("}\n"));
+
+    let mermaid = graph::to_mermaid(&s);
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("g1 -->|\"Bake\"| g0"), "{}", mermaid);
+    assert!(mermaid.contains("class g1 unproduced"), "{}", mermaid);
+}
+
+#[test]
+fn only_consumed_goods_without_a_producer_are_flagged() {
+    let mut b = StructureBuilder::new();
+    b.good("ore", 1.0);
+    b.good("tool", 1.0);
+    b.good("gem", 1.0);
+    b.good("trinket", 1.0);
+    b.need("use_tool", "tool", 1.0, 5);
+    b.need("admire", "gem", 1.0, 5);
+    b.household_type("smith", 10.0).need("use_tool").need("admire");
+    b.rule("mine", 2).output("ore", 1.0);
+    b.rule("forge", 3).input("ore", 1.0).output("tool", 1.0);
+    let s = b.build().unwrap();
+
+    let flagged: Vec<&str> = graph::unproduced_goods(&s).into_iter().map(|g| s.good_name(g)).collect();
+    assert_eq!(flagged, vec!["gem"]);
+    assert!(graph::to_dot(&s).contains("r0 -> g0;"));
+    assert!(graph::to_mermaid(&s).contains("r0{{\"mine\"}} --> g0"));
+}
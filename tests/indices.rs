@@ -0,0 +1,60 @@
+//! Chain-linked market indices across price moves, listings and splits.
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::{CorporateActionKind, IndexWeighting};
+use economy_sim::data::ids::IndexId;
+use economy_sim::simulation::engine::Simulation;
+
+/// Stocks at 10 and 20 and a late listing, tracked by one index per weighting.
+/// Without households or earnings nothing moves prices but the test.
+fn market() -> Simulation {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 1.0);
+    b.stock("a", 10.0, 100);
+    b.stock("b", 20.0, 300);
+    b.stock("late", 40.0, 0).corporate_action(CorporateActionKind::Ipo, 2, 100.0).corporate_action(CorporateActionKind::Split, 4, 2.0);
+    b.index("price", IndexWeighting::Price).base_value(100.0);
+    b.index("cap", IndexWeighting::Cap).base_value(1000.0);
+    b.index("equal", IndexWeighting::Equal).base_value(100.0);
+    Simulation::new(b.build().unwrap(), 1, 0)
+}
+
+fn assert_levels(sim: &Simulation, expected: &[f64]) {
+    assert_eq!(sim.indices.value.len(), expected.len());
+    for (v, e) in sim.indices.value.iter().zip(expected) {
+        assert!((v - e).abs() < 1e-9, "{:?} != {:?}", sim.indices.value, expected);
+    }
+}
+
+#[test]
+fn levels_follow_weighted_price_changes() {
+    let mut sim = market();
+    sim.tick();
+    assert_levels(&sim, &[100.0, 1000.0, 100.0]);
+
+    sim.stock_market.price[0] = 11.0;
+    sim.indices.update(&sim.structure, &sim.stock_market);
+    // Price: 31 / 30. Cap: (1100 + 6000) / 7000. Equal: the mean of +10% and 0%.
+    assert_levels(&sim, &[100.0 * 31.0 / 30.0, 1000.0 * 7100.0 / 7000.0, 105.0]);
+    assert!((sim.indices.change(IndexId(2)) - 0.05).abs() < 1e-12);
+}
+
+#[test]
+fn listings_and_splits_do_not_move_levels() {
+    let mut sim = market();
+    sim.tick();
+    let before = sim.indices.value.clone();
+    // The listing on tick 2 and the 2:1 split on tick 4 change prices, shares
+    // outstanding and the member set, but no member's value.
+    for _ in 0..4 {
+        sim.tick();
+        assert_levels(&sim, &before);
+    }
+    assert_eq!(sim.stock_market.price[2], 20.0);
+    assert_eq!(sim.stock_market.outstanding[2], 200.0);
+
+    // From then on the new member counts like any other.
+    sim.stock_market.price[2] = 22.0;
+    sim.indices.update(&sim.structure, &sim.stock_market);
+    assert!((sim.indices.value[0] - before[0] * 52.0 / 50.0).abs() < 1e-9);
+}
@@ -0,0 +1,90 @@
+//! Household insolvency: liquidation, debt write-down and the resolutions.
+
+mod common;
+
+use common::tick_conserving_cash;
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::InsolvencyResolution;
+use economy_sim::data::ids::GoodId;
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::events::EventKind;
+use economy_sim::simulation::household::HouseholdId;
+
+/// `n` households too poor to trade, each holding 5 bread at 2 and 10 shares
+/// at 10, declared bankrupt on the second tick their equity is negative. Half
+/// of any debt left after liquidation is written off.
+fn debtors(resolution: InsolvencyResolution, n: usize) -> Simulation {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 2.0);
+    b.stock("acme", 10.0, 1000);
+    b.household_type("debtor", 100.0).inventory("bread", 5.0).shares("acme", 10.0);
+    b.insolvency(1, resolution, None, 0.5);
+    Simulation::new(b.build().unwrap(), 1, n)
+}
+
+/// Bankruptcies logged this tick as (household, liquidated, written off).
+fn bankruptcies(sim: &Simulation) -> Vec<(u32, f64, f64)> {
+    let mut found: Vec<(u32, f64, f64)> = sim
+        .events
+        .events
+        .iter()
+        .filter(|e| e.tick == sim.tick)
+        .filter_map(|e| match e.kind {
+            EventKind::HouseholdBankruptcy { household, liquidated, written_off, .. } => Some((household.0, liquidated, written_off)),
+            _ => None,
+        })
+        .collect();
+    found.sort_by_key(|f| f.0);
+    found
+}
+
+#[test]
+fn bankrupt_households_are_liquidated_and_written_down() {
+    let mut sim = debtors(InsolvencyResolution::Liquidate, 3);
+    sim.households.margin_debt[1] = 500.0;
+    sim.households.cash[2] = -300.0;
+    // Household 1 is margin called first: its shares repay 200 of the loan.
+    tick_conserving_cash(&mut sim);
+    assert!(bankruptcies(&sim).is_empty());
+    assert_eq!(sim.households.margin_debt[1], 300.0);
+    tick_conserving_cash(&mut sim);
+
+    // Household 1's bread repays another 10 and half of the other 290 is
+    // written off. Household 2's bread and shares fetch 110, leaving it 190
+    // short, and it owes half of that.
+    assert_eq!(bankruptcies(&sim), [(1, 0.0, 145.0), (2, 110.0, 95.0)]);
+    assert_eq!(sim.events.household_bankruptcies, 2);
+    let h = sim.households.get(1);
+    assert_eq!((h.cash, h.margin_debt, h.good(GoodId(0))), (0.0, 145.0, 0.0));
+    assert!(h.portfolio.iter().all(|&q| q == 0.0));
+    assert_eq!(sim.households.cash[2], -95.0);
+    assert_eq!(sim.cash_flows.write_offs, 95.0);
+    assert_eq!(sim.households.distress_ticks, [0, 0, 0]);
+    // The solvent household is left alone.
+    assert_eq!((sim.households.cash[0], sim.households.portfolio[0]), (100.0, 10.0));
+}
+
+#[test]
+fn removed_households_leave_the_rest_in_order() {
+    let mut sim = debtors(InsolvencyResolution::Remove, 5);
+    for i in 0..5 {
+        sim.households.inventory[i] = i as f64;
+    }
+    sim.households.cash[1] = -300.0;
+    sim.households.cash[3] = -300.0;
+    tick_conserving_cash(&mut sim);
+    tick_conserving_cash(&mut sim);
+
+    assert_eq!(bankruptcies(&sim).iter().map(|b| b.0).collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(sim.households.id, [HouseholdId(0), HouseholdId(2), HouseholdId(4)]);
+    assert_eq!(sim.households.inventory, [0.0, 2.0, 4.0]);
+    assert_eq!(sim.households.margin_debt, [0.0, 0.0, 0.0]);
+    assert_eq!(sim.households.portfolio, [10.0, 10.0, 10.0]);
+    assert_eq!(sim.households.position(HouseholdId(4)), Some(2));
+    assert_eq!(sim.households.position(HouseholdId(3)), None);
+    // The removed households' shares went back to the market.
+    assert_eq!(sim.stock_market.held[0], 30.0);
+
+    tick_conserving_cash(&mut sim);
+    assert_eq!(sim.households.len(), 3);
+}
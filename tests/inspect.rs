@@ -0,0 +1,103 @@
+//! The household inspector's history: sparkline series, timeline and need streaks.
+
+mod common;
+
+use common::fixture;
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::InsolvencyResolution;
+use economy_sim::data::ids::{GoodId, NeedId};
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::events::EventKind;
+use economy_sim::simulation::household::HouseholdId;
+use economy_sim::simulation::inspect::Activity;
+
+#[test]
+fn timeline_records_needs_and_purchases() {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 2.0);
+    b.need("need_bread", "bread", 1.0, 0);
+    b.household_type("cook", 100.0).inventory("bread", 1.0).need("need_bread");
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 2);
+    sim.inspect(Some(HouseholdId(1)));
+    for _ in 0..6 {
+        sim.tick();
+    }
+
+    // The need comes due every second tick: eaten, missed and bought, then
+    // bought again while unmet and eaten.
+    let i = sim.inspector.as_ref().unwrap();
+    let bread = GoodId(0);
+    let timeline: Vec<(u64, Activity)> = i.timeline.iter().cloned().collect();
+    assert_eq!(timeline[..3], [
+        (2, Activity::Consumed { good: bread, qty: 1.0 }),
+        (4, Activity::MissedNeed { need: NeedId(0) }),
+        (4, Activity::Bought { good: bread, qty: 1.0, cost: 2.0 }),
+    ]);
+    assert!(matches!(timeline[3], (5, Activity::Bought { .. })));
+    assert_eq!(timeline[4], (6, Activity::Consumed { good: bread, qty: 1.0 }));
+    assert_eq!(timeline.len(), 5);
+    assert_eq!(i.streaks, [1]);
+    assert_eq!(i.cash.len(), 6);
+    assert_eq!(i.cash[3], 98.0);
+    assert_eq!(*i.utility.back().unwrap(), sim.households.utility[1]);
+
+    // Inspecting another household starts over; inspecting the same one does not.
+    sim.inspect(Some(HouseholdId(1)));
+    assert_eq!(sim.inspector.as_ref().unwrap().cash.len(), 6);
+    sim.inspect(Some(HouseholdId(0)));
+    assert!(sim.inspector.as_ref().unwrap().cash.is_empty());
+}
+
+#[test]
+fn inspection_does_not_change_the_outcome() {
+    let mut plain = Simulation::new(fixture("basic"), 11, 200);
+    let bankrupt = loop {
+        plain.tick();
+        let found = plain.events.events.iter().find_map(|e| match e.kind {
+            EventKind::HouseholdBankruptcy { household, .. } if e.tick == plain.tick => Some(household),
+            _ => None,
+        });
+        if let Some(h) = found {
+            break h;
+        }
+        assert!(plain.tick < 1000, "nobody went bankrupt");
+    };
+
+    let mut watched = Simulation::new(fixture("basic"), 11, 200);
+    watched.inspect(Some(bankrupt));
+    while watched.tick < plain.tick {
+        watched.tick();
+    }
+    assert_eq!(watched.state_hash(), plain.state_hash());
+
+    let i = watched.inspector.as_ref().unwrap();
+    let last: Vec<&Activity> = i.timeline.iter().filter(|(t, _)| *t == plain.tick).map(|(_, a)| a).collect();
+    assert!(last.iter().any(|a| matches!(a, Activity::Event(text) if text.contains("bankrupt"))), "{:?}", last);
+    assert!(last.iter().any(|a| matches!(a, Activity::Transfer { .. })), "{:?}", last);
+    assert_eq!(*i.cash.back().unwrap(), watched.households.cash[bankrupt.0 as usize]);
+}
+
+#[test]
+fn removed_households_end_their_history() {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 2.0);
+    b.household_type("debtor", 100.0).inventory("bread", 5.0);
+    b.insolvency(1, InsolvencyResolution::Remove, None, 0.5);
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 4);
+    // Removing household 1 shifts household 2 onto the watched row 2.
+    sim.households.cash[1] = -300.0;
+    sim.households.cash[2] = -300.0;
+    sim.inspect(Some(HouseholdId(2)));
+    sim.tick();
+    sim.tick();
+    assert_eq!(sim.households.id, [HouseholdId(0), HouseholdId(3)]);
+
+    let i = sim.inspector.as_ref().unwrap();
+    let last: Vec<&Activity> = i.timeline.iter().filter(|(t, _)| *t == sim.tick).map(|(_, a)| a).collect();
+    assert!(matches!(last[..], [Activity::Event(ref text), Activity::Removed] if text.contains("bankrupt")), "{:?}", last);
+    assert_eq!(i.cash, [-300.0]);
+    let recorded = i.timeline.len();
+    // Later ticks record nothing more.
+    sim.tick();
+    assert_eq!(sim.inspector.as_ref().unwrap().timeline.len(), recorded);
+}
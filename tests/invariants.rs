@@ -0,0 +1,116 @@
+//! Economic invariants on the `tests/fixtures/basic` economy.
+
+mod common;
+
+use common::{check_invariants, fixture, tick_conserving_cash};
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::events::EventKind;
+
+fn basic(households: usize) -> Simulation {
+    Simulation::new(fixture("basic"), 11, households)
+}
+
+#[test]
+fn cash_moves_only_through_recorded_flows() {
+    let mut sim = basic(200);
+    for _ in 0..400 {
+        tick_conserving_cash(&mut sim);
+    }
+    // The run covers every kind of flow the fixture can produce.
+    assert!(sim.events.firm_bankruptcies > 0, "bust never failed");
+    assert!(sim.events.household_bankruptcies > 0, "nobody went bankrupt");
+}
+
+#[test]
+fn force_sell_is_recorded() {
+    let mut sim = basic(50);
+    for _ in 0..30 {
+        sim.tick();
+    }
+    let before = common::total_cash(&sim);
+    let recorded = sim.cash_flows.total();
+    sim.force_sell_all_stocks();
+    let moved = common::total_cash(&sim) - before;
+    assert!((moved - (sim.cash_flows.total() - recorded)).abs() < 1e-9 * (1.0 + before.abs()));
+    assert!(sim.households.iter().all(|h| h.portfolio.iter().all(|&q| q <= 0.0)));
+}
+
+#[test]
+fn state_invariants_hold_every_tick() {
+    let mut sim = basic(200);
+    for _ in 0..400 {
+        sim.tick();
+        check_invariants(&sim);
+    }
+}
+
+#[test]
+fn need_timers_count_down_and_reset_to_interval() {
+    let mut sim = basic(40);
+    let s = sim.structure.clone();
+    for _ in 0..50 {
+        let before: Vec<Vec<u64>> = sim.households.iter().map(|h| h.next_due_in.to_vec()).collect();
+        sim.tick();
+        // Households reset after bankruptcy start over with fresh timers.
+        let reset: Vec<u32> = sim
+            .events
+            .events
+            .iter()
+            .filter(|e| e.tick == sim.tick)
+            .filter_map(|e| match e.kind {
+                EventKind::HouseholdBankruptcy { household, .. } => Some(household.0),
+                _ => None,
+            })
+            .collect();
+        for (i, h) in sim.households.iter().enumerate() {
+            if reset.contains(&h.id.0) {
+                continue;
+            }
+            for (slot, &nid) in s.household_types[h.kind.0 as usize].needs.iter().enumerate() {
+                let interval = s.needs[nid.0 as usize].interval_ticks;
+                let expected = if before[i][slot] == 0 { interval } else { before[i][slot] - 1 };
+                assert_eq!(h.next_due_in[slot], expected, "household {} slot {} at tick {}", h.id.0, slot, sim.tick);
+            }
+        }
+    }
+}
+
+#[test]
+fn needs_are_served_in_priority_order() {
+    let s = fixture("basic");
+    for td in &s.household_types {
+        let priorities: Vec<i32> = td.needs.iter().map(|n| s.needs[n.0 as usize].priority).collect();
+        assert!(priorities.windows(2).all(|w| w[0] <= w[1]), "{} needs out of order", td.display_name);
+    }
+}
+
+#[test]
+fn decay_removes_a_fixed_fraction_each_tick() {
+    let mut sim = basic(100);
+    let hoarder = sim.structure.household_type_ids.map["hoarder"];
+    let bread = sim.structure.good_ids.map["bread"] as usize;
+    let rate = sim.structure.goods[bread].decay_rate;
+
+    let mut expected = 10.0;
+    for _ in 0..25 {
+        sim.tick();
+        expected -= expected * rate;
+        let hoarders: Vec<_> = sim.households.iter().filter(|h| h.kind.0 == hoarder).collect();
+        assert!(!hoarders.is_empty());
+        for h in hoarders {
+            assert_eq!(h.inventory[bread], expected, "tick {}", sim.tick);
+        }
+    }
+    assert!((expected - 10.0 * (1.0 - rate).powi(25)).abs() < 1e-9);
+}
+
+#[test]
+fn unaffordable_needs_still_count_as_demand() {
+    let mut sim = basic(100);
+    for _ in 0..10 {
+        sim.tick();
+    }
+    let demand: f64 = sim.market.demand.iter().sum();
+    assert!(demand > 0.0);
+    assert!(sim.cash_flows.goods <= 0.0);
+}
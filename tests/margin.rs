@@ -0,0 +1,81 @@
+//! Margin loans, short selling, borrow fees and margin calls.
+
+mod common;
+
+use common::tick_conserving_cash;
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::ids::StockId;
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::events::EventKind;
+
+/// One stock at 10 and households holding `shares` of it, with a broker
+/// lending up to 2x and charging `fee` per tick on shorts.
+fn broker(cash: f64, shares: f64, fee: f64) -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 1.0);
+    b.stock("acme", 10.0, 1000);
+    b.stock("bust", 10.0, 1000);
+    b.margin(0.5, 0.25, fee, 0.0);
+    b.household_type("trader", cash).shares("acme", shares).max_leverage(3.0);
+    b
+}
+
+#[test]
+fn purchases_borrow_up_to_the_broker_leverage() {
+    let mut sim = Simulation::new(broker(1000.0, 0.0, 0.0).build().unwrap(), 1, 1);
+    tick_conserving_cash(&mut sim);
+    // The budget is 1% of cash above 150, times the leverage: the archetype's
+    // 3x is capped at the broker's 2x, so half the cost is borrowed.
+    let h = sim.households.get(0);
+    let held: f64 = h.portfolio.iter().sum();
+    assert!((held - 1.7).abs() < 1e-12, "held {}", held);
+    assert!((h.margin_debt - 8.5).abs() < 1e-12);
+    assert!((h.cash - 991.5).abs() < 1e-12);
+}
+
+#[test]
+fn shorts_pay_borrow_fees() {
+    let mut sim = Simulation::new(broker(100.0, 0.0, 0.01).build().unwrap(), 1, 1);
+    sim.households.portfolio[1] = -5.0;
+    sim.stock_market.short_interest[1] = 5.0;
+    tick_conserving_cash(&mut sim);
+    assert!((sim.cash_flows.borrow_fees + 0.5).abs() < 1e-12, "fees {}", sim.cash_flows.borrow_fees);
+    assert!((sim.households.cash[0] - 99.5).abs() < 1e-12);
+}
+
+#[test]
+fn margin_calls_liquidate_positions() {
+    let mut sim = Simulation::new(broker(100.0, 20.0, 0.0).build().unwrap(), 1, 1);
+    sim.households.margin_debt[0] = 200.0;
+    sim.stock_market.price[0] = 5.0;
+    // Equity 100 + 20 × 5 − 200 = 0 is below a quarter of the exposure of 100.
+    tick_conserving_cash(&mut sim);
+    let h = sim.households.get(0);
+    assert_eq!(h.shares(StockId(0)), 0.0);
+    // The proceeds and the cash on hand just repay the loan.
+    assert_eq!((h.cash, h.margin_debt), (0.0, 0.0));
+    assert_eq!(sim.margin_calls, 1);
+    assert!(matches!(sim.events.events.back().unwrap().kind, EventKind::MarginCall { .. }));
+}
+
+#[test]
+fn force_sell_closes_longs_and_shorts() {
+    let mut sim = Simulation::new(broker(1000.0, 20.0, 0.0).build().unwrap(), 1, 3);
+    for i in 0..3 {
+        sim.households.portfolio[i * 2 + 1] = -5.0;
+        sim.households.margin_debt[i] = 50.0;
+    }
+    sim.stock_market.short_interest[1] = 15.0;
+    let before = common::total_cash(&sim);
+    sim.cash_flows = Default::default();
+    sim.force_sell_all_stocks();
+    assert!((common::total_cash(&sim) - before - sim.cash_flows.total()).abs() < 1e-9);
+
+    let prices = &sim.stock_market.price;
+    for h in sim.households.iter() {
+        assert!(h.portfolio.iter().all(|&q| q == 0.0), "{:?}", h.portfolio);
+        assert_eq!(h.margin_debt, 0.0);
+        assert_eq!(h.equity(prices), h.cash);
+    }
+    assert_eq!((sim.stock_market.held[0], sim.stock_market.short_interest[1]), (0.0, 0.0));
+}
@@ -0,0 +1,59 @@
+//! Layering mod directories over `tests/fixtures/basic`.
+
+mod common;
+
+use economy_sim::data::compiled::Structure;
+use economy_sim::data::xml;
+
+fn load(mods: &[&str]) -> anyhow::Result<(Structure, Vec<String>)> {
+    let mut cfg = common::fixture_config("basic");
+    cfg.mods = mods.iter().map(|m| format!("tests/fixtures/mods/{}", m)).collect();
+    let (raw, conflicts) = xml::load_all_with_conflicts(&cfg)?;
+    Ok((Structure::compile(raw)?, conflicts.iter().map(|c| c.to_string()).collect()))
+}
+
+#[test]
+fn mods_add_override_and_remove_entries() {
+    let (s, conflicts) = load(&["salt"]).unwrap();
+    assert!(conflicts.is_empty(), "{:?}", conflicts);
+
+    let salt = s.good_ids.map["salt"] as usize;
+    assert_eq!(s.goods[salt].base_price, 0.5);
+    let bread = &s.goods[s.good_ids.map["bread"] as usize];
+    assert_eq!(bread.decay_rate, 0.05);
+    assert_eq!(bread.base_price, 5.0, "fields a mod doesn't list keep their base value");
+    assert_eq!(bread.display_name, "Bread");
+
+    let trader = &s.household_types[s.household_type_ids.map["trader"] as usize];
+    assert_eq!(trader.needs.len(), 3);
+    assert_eq!(trader.starting_cash, 800.0);
+    assert!(!s.household_type_ids.map.contains_key("hoarder"));
+    assert_eq!(s.household_types.len(), 3);
+}
+
+#[test]
+fn later_mods_win_and_clashes_are_reported() {
+    let (s, conflicts) = load(&["salt", "famine"]).unwrap();
+    let bread = &s.goods[s.good_ids.map["bread"] as usize];
+    assert_eq!(bread.decay_rate, 0.2);
+    assert_eq!(bread.base_price, 15.0);
+    assert_eq!(s.margin.maintenance_margin, 0.5);
+    assert_eq!(s.margin.initial_margin, 0.5, "unlisted margin fields are kept");
+
+    assert_eq!(conflicts.len(), 1, "{:?}", conflicts);
+    assert!(conflicts[0].contains("good bread decay_rate"), "{}", conflicts[0]);
+    assert!(conflicts[0].contains("famine"));
+}
+
+#[test]
+fn removing_an_unknown_entry_fails() {
+    let err = load(&["bad"]).unwrap_err();
+    assert!(format!("{:#}", err).contains("need need_sleep"), "{:#}", err);
+}
+
+#[test]
+fn no_mods_leaves_data_untouched() {
+    let (s, conflicts) = load(&[]).unwrap();
+    assert!(conflicts.is_empty());
+    assert_eq!(s.goods.len(), common::fixture("basic").goods.len());
+}
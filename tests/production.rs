@@ -0,0 +1,266 @@
+//! Production rules: capacity, seasonal factors, random yields, supply shocks
+//! and multi-stage chains with capital and labor.
+
+mod common;
+
+use common::tick_conserving_cash;
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::{GoodCategory, Structure, YieldDist};
+use economy_sim::data::ids::{GoodId, HouseholdTypeId, RuleId};
+use economy_sim::data::xml::InventoryItemXml;
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::events::EventKind;
+use economy_sim::simulation::market::Market;
+use economy_sim::simulation::production::{Bottleneck, Production};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// One good produced from nothing by households that neither need nor hold it,
+/// so the market's supply is exactly the rule's output.
+fn farm() -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.good("grain", 10.0);
+    b.household_type("idle", 100.0);
+    b
+}
+
+fn supply_per_tick(mut sim: Simulation, ticks: usize) -> Vec<f64> {
+    (0..ticks)
+        .map(|_| {
+            sim.tick();
+            sim.market.supply[0]
+        })
+        .collect()
+}
+
+#[test]
+fn capacity_and_seasons_scale_output() {
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 2.0).capacity(5.0).seasonality(8, &[1.0, 0.5, 2.0, 0.0]);
+    let s = b.build().unwrap();
+    let season = s.production_rules[0].seasonality.clone().unwrap();
+    assert_eq!((0..8).map(|t| season.factor(t)).collect::<Vec<_>>(), [1.0, 1.0, 0.5, 0.5, 2.0, 2.0, 0.0, 0.0]);
+
+    // The first tick run is tick 1.
+    let supply = supply_per_tick(Simulation::new(s, 1, 10), 8);
+    assert_eq!(supply, [10.0, 5.0, 5.0, 20.0, 20.0, 0.0, 0.0, 10.0]);
+}
+
+#[test]
+fn random_yields_are_seeded() {
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0).capacity(100.0).yield_normal(1.0, 0.2);
+    let s = b.build().unwrap();
+    assert_eq!(s.production_rules[0].yield_dist, YieldDist::Normal { mean: 1.0, sd: 0.2 });
+
+    let a = supply_per_tick(Simulation::new(s.clone(), 7, 10), 200);
+    let again = supply_per_tick(Simulation::new(s.clone(), 7, 10), 200);
+    let other = supply_per_tick(Simulation::new(s, 8, 10), 200);
+    assert_eq!(a, again);
+    assert_ne!(a, other);
+    let mean = a.iter().sum::<f64>() / a.len() as f64;
+    assert!((mean - 100.0).abs() < 5.0, "mean output {}", mean);
+    assert!(a.iter().any(|&q| q < 90.0) && a.iter().any(|&q| q > 110.0));
+
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0).capacity(10.0).yield_uniform(0.5, 1.5);
+    let supply = supply_per_tick(Simulation::new(b.build().unwrap(), 3, 10), 200);
+    assert!(supply.iter().all(|&q| (5.0..15.0).contains(&q)));
+}
+
+#[test]
+fn shocks_cut_output_for_their_duration() {
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0).capacity(10.0);
+    b.rule("mine", 1).output("grain", 1.0).capacity(1.0);
+    b.shock("blight", 1.0, 5, 0.5).display_name("Blight").rule("grow");
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 10);
+
+    sim.tick();
+    assert_eq!(sim.market.supply[0], 6.0);
+    let started: Vec<u64> = sim
+        .events
+        .events
+        .iter()
+        .filter_map(|e| match e.kind {
+            EventKind::SupplyShock { until, .. } => Some(until),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(started, [6]);
+    assert!(sim.events.events.back().unwrap().describe(&sim.structure).starts_with("Blight: output at 50% until"));
+
+    // Chance 1.0 restarts the shock on the tick it ends.
+    let supply = supply_per_tick(sim, 5);
+    assert_eq!(supply, [6.0, 6.0, 6.0, 6.0, 11.0]);
+}
+
+#[test]
+fn invalid_production_settings_are_rejected() {
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0).seasonality(10, &[]);
+    assert!(b.build().unwrap_err().to_string().contains("seasonality"));
+
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0).yield_normal(1.0, -1.0);
+    assert!(b.build().unwrap_err().to_string().contains("invalid normal yield"));
+
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0);
+    b.shock("blight", 0.1, 5, 0.5).rule("harvest");
+    assert!(b.build().unwrap_err().to_string().contains("shock blight references unknown rule harvest"));
+}
+
+#[test]
+fn migration_keeps_active_shocks() {
+    let mut b = farm();
+    b.rule("grow", 1).output("grain", 1.0).capacity(10.0);
+    b.shock("blight", 1.0, 50, 0.5).rule("grow");
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 10);
+    sim.tick();
+    let hash = sim.state_hash();
+
+    let mut b = farm();
+    b.rule("fish", 1).output("grain", 1.0);
+    b.rule("grow", 1).output("grain", 1.0).capacity(10.0);
+    b.shock("flood", 0.0, 5, 0.1);
+    b.shock("blight", 1.0, 50, 0.5).rule("grow");
+    let report = sim.migrate(b.build().unwrap()).unwrap();
+    assert!(report.added.iter().any(|a| a == "shock flood"), "{}", report);
+    assert_eq!(sim.production.shock_until, [None, Some(51)]);
+    assert_eq!(sim.production.last_multiplier, [1.0, 0.5]);
+    assert_ne!(sim.state_hash(), hash);
+    sim.tick();
+    assert_eq!(sim.market.supply[0], 6.0);
+}
+
+/// Grain grown by `farm` feeds `mill`; households supply ten workers.
+fn chain(farm_capacity: f64) -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0);
+    b.good("grain", 2.0).category(GoodCategory::Food);
+    b.good("plough", 50.0).category(GoodCategory::Capital).decay_rate(0.5);
+    b.household_type("worker", 100.0).labor(1.0);
+    // Listed downstream first: the engine must still run the farm first.
+    b.rule("mill", 1).input("grain", 1.0).output("bread", 1.0).capacity(20.0);
+    b.rule("farm", 1).output("grain", 1.0).capacity(farm_capacity);
+    b
+}
+
+#[test]
+fn upstream_shortages_cap_downstream_runs() {
+    let s = chain(8.0).build().unwrap();
+    let [mill, farm] = [0, 1].map(RuleId);
+    assert_eq!(s.rule_order, [farm, mill]);
+    assert_eq!(s.intermediate, [false, true, false]);
+
+    let mut sim = Simulation::new(s, 1, 10);
+    sim.tick();
+    // Grain never reaches the market; the mill turns all of it into bread.
+    assert_eq!((sim.market.supply[1], sim.market.demand[1]), (0.0, 0.0));
+    assert_eq!(sim.market.supply[0], 8.0);
+    assert_eq!(sim.production.utilization[mill.0 as usize], 0.4);
+    assert_eq!(sim.production.bottleneck[mill.0 as usize], Bottleneck::Input(GoodId(1)));
+    assert_eq!(sim.production.bottleneck[farm.0 as usize], Bottleneck::None);
+    assert_eq!(sim.production.stock[1], 0.0);
+}
+
+#[test]
+fn labor_goes_upstream_first() {
+    let mut raw = chain(8.0).into_raw();
+    raw.production.rules[0].labor = 1.0;
+    raw.production.rules[1].labor = 0.5;
+    let mut sim = Simulation::new(Structure::compile(raw).unwrap(), 1, 10);
+    sim.tick();
+    // The farm takes 4 of the 10 workers, leaving the mill 6 of the 20 it wants.
+    assert_eq!((sim.production.labor_supply, sim.production.labor_used), (10.0, 10.0));
+    assert_eq!(sim.production.utilization[1], 1.0);
+    assert_eq!(sim.production.utilization[0], 0.3);
+    assert_eq!(sim.production.bottleneck[0], Bottleneck::Labor);
+}
+
+#[test]
+fn wages_are_paid_by_workers_offered() {
+    let mut b = chain(8.0);
+    b.household_type("part_time", 100.0).labor(0.5);
+    let mut raw = b.into_raw();
+    raw.production.rules[0].labor = 1.0;
+    raw.production.rules[0].wage = 0.5;
+    raw.production.rules[1].labor = 0.5;
+    raw.production.rules[1].wage = 1.0;
+    let mut sim = Simulation::new(Structure::compile(raw).unwrap(), 1, 4);
+    sim.households.kind = vec![HouseholdTypeId(0), HouseholdTypeId(0), HouseholdTypeId(1), HouseholdTypeId(1)];
+    tick_conserving_cash(&mut sim);
+    // The farm employs all 3 workers on offer at 1 each, leaving the mill none.
+    assert_eq!(sim.production.wages, 3.0);
+    assert_eq!(sim.cash_flows.wages, 3.0);
+    assert_eq!(sim.households.cash, [101.0, 101.0, 100.5, 100.5]);
+}
+
+#[test]
+fn capital_depreciates_and_is_replaced() {
+    // Ploughs nobody makes can only be bought from what the market is offered.
+    let mut raw = chain(8.0).into_raw();
+    raw.production.rules[1].capital.items.push(InventoryItemXml { good_ref: "plough".into(), qty: 0.5 });
+    let s = Structure::compile(raw.clone()).unwrap();
+    let (mut p, mut market, mut rng) = (Production::new(&s), Market::new(&s), ChaCha8Rng::seed_from_u64(1));
+    assert_eq!(p.capital[1], [4.0]);
+    p.run(&s, 1, 10.0, &mut market, &mut rng);
+    // Half wore out and none were offered.
+    assert_eq!(market.demand[2], 0.0);
+    assert_eq!(p.capital[1], [2.0]);
+    assert_eq!(p.utilization[1], 0.5);
+    assert_eq!(p.bottleneck[1], Bottleneck::Capital(GoodId(2)));
+
+    market.reset_pressures();
+    market.note_supply(GoodId(2), 1.5);
+    market.note_demand(GoodId(2), 0.5);
+    p.run(&s, 2, 10.0, &mut market, &mut rng);
+    // One more wore out; the one offered beyond other buyers replaced it.
+    assert_eq!(market.demand[2], 1.5);
+    assert_eq!(p.capital[1], [2.0]);
+
+    // Made by a rule, they come from its output only, so a slow forge starves the farm.
+    let mut b = StructureBuilder::from_raw(raw);
+    b.rule("forge", 1).output("plough", 1.0);
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 10);
+    sim.tick();
+    assert_eq!(sim.market.demand[2], 0.0);
+    // Half the four ploughs wore out; the forge, running first, replaced one.
+    assert_eq!(sim.production.capital[1], [3.0]);
+    assert_eq!(sim.production.utilization[1], 0.75);
+    assert_eq!(sim.production.bottleneck[1], Bottleneck::Capital(GoodId(2)));
+    sim.tick();
+    assert_eq!(sim.production.capital[1], [2.5]);
+    assert_eq!(sim.production.stock[2], 0.0);
+}
+
+#[test]
+fn invalid_chains_are_rejected() {
+    let mut b = chain(8.0);
+    b.rule("sow", 1).input("bread", 1.0).output("grain", 1.0);
+    b.rule("bake", 1).input("grain", 1.0).output("bread", 1.0);
+    let err = b.build().unwrap_err().to_string();
+    assert!(err.contains("production rules form a cycle"), "{}", err);
+
+    let mut b = chain(8.0);
+    b.rule("till", 1).capital("grain", 1.0).output("bread", 1.0);
+    assert!(b.build().unwrap_err().to_string().contains("rule till capital grain is not a capital good"));
+}
+
+#[test]
+fn migration_keeps_capital_and_stock() {
+    // Half the six ploughs wear out and nobody sells more, so 30 grain is grown.
+    let mut raw = chain(60.0).into_raw();
+    raw.production.rules[1].capital.items.push(InventoryItemXml { good_ref: "plough".into(), qty: 0.1 });
+    let mut sim = Simulation::new(Structure::compile(raw.clone()).unwrap(), 1, 10);
+    sim.tick();
+    assert_eq!(sim.production.stock[1], 10.0);
+
+    let mut b = StructureBuilder::from_raw(raw);
+    b.good("salt", 1.0);
+    b.rule("evaporate", 1).output("salt", 1.0);
+    sim.migrate(b.build().unwrap()).unwrap();
+    assert_eq!(sim.production.stock, [0.0, 10.0, 0.0, 0.0]);
+    assert_eq!(sim.production.capital, [vec![], vec![3.0], vec![]]);
+}
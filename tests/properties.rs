@@ -0,0 +1,64 @@
+//! Property tests over randomly shaped economies from `SyntheticSpec`.
+
+mod common;
+
+use common::{check_invariants, tick_conserving_cash};
+use economy_sim::data::compiled::Structure;
+use economy_sim::data::synthetic::SyntheticSpec;
+use economy_sim::simulation::engine::Simulation;
+use proptest::prelude::*;
+use proptest::test_runner::FileFailurePersistence;
+
+fn spec() -> impl Strategy<Value = SyntheticSpec> {
+    (1usize..8, 1usize..16, 1usize..6, 1usize..6, 0usize..6, 0usize..6, any::<u64>()).prop_map(
+        |(goods, needs, household_types, needs_per_type, rules, stocks, seed)| SyntheticSpec {
+            goods,
+            needs,
+            household_types,
+            needs_per_type,
+            rules,
+            stocks,
+            seed,
+        },
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn generated_economies_compile(spec in spec()) {
+        let s = Structure::compile(spec.generate().parse().unwrap()).unwrap();
+        prop_assert_eq!(s.goods.len(), spec.goods);
+        prop_assert_eq!(s.household_types.len(), spec.household_types);
+        for td in &s.household_types {
+            prop_assert!(td.needs.len() <= spec.needs_per_type);
+        }
+    }
+
+    #[test]
+    fn invariants_hold_for_generated_economies(spec in spec(), seed in any::<u64>(), households in 1usize..60) {
+        let s = Structure::compile(spec.generate().parse().unwrap()).unwrap();
+        let mut sim = Simulation::new(s, seed, households);
+        for _ in 0..60 {
+            tick_conserving_cash(&mut sim);
+            check_invariants(&sim);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_state(spec in spec(), seed in any::<u64>()) {
+        let s = Structure::compile(spec.generate().parse().unwrap()).unwrap();
+        let mut a = Simulation::new(s.clone(), seed, 20);
+        let mut b = Simulation::new(s, seed, 20);
+        for _ in 0..20 {
+            a.tick();
+            b.tick();
+        }
+        prop_assert_eq!(a.state_hash(), b.state_hash());
+    }
+}
@@ -0,0 +1,69 @@
+//! Household browser queries: filters, sort orders and id search.
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::ids::HouseholdTypeId;
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::simulation::query::{search_id, Filter, HouseholdQuery, SortKey};
+
+/// Twelve households alternating rich and poor, with cash 10 × id and bread worth 5 each.
+fn town() -> Simulation {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 5.0);
+    b.household_type("rich", 0.0);
+    b.household_type("poor", 0.0);
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 12);
+    for i in 0..12 {
+        sim.households.kind[i] = HouseholdTypeId(i as u32 % 2);
+        sim.households.cash[i] = 10.0 * i as f64;
+        sim.households.utility[i] = -(i as f64 % 3.0);
+        sim.households.inventory[i] = (12 - i) as f64;
+    }
+    sim
+}
+
+fn query(sim: &Simulation, filter: &str, sort: SortKey, descending: bool) -> Vec<usize> {
+    let filter = Filter::parse(filter, &sim.structure).unwrap();
+    HouseholdQuery { filter, sort, descending }.run(sim).into_iter().map(|id| id.0 as usize).collect()
+}
+
+#[test]
+fn filters_combine_conditions() {
+    let sim = town();
+    assert_eq!(query(&sim, "", SortKey::Id, false), (0..12).collect::<Vec<_>>());
+    assert_eq!(query(&sim, "type=poor cash<50", SortKey::Id, false), [1, 3]);
+    assert_eq!(query(&sim, "type!=poor cash>=50 utility=0", SortKey::Id, false), [6]);
+    // Net worth is cash plus bread at market price: 10i + 5(12 - i) = 60 + 5i.
+    assert_eq!(query(&sim, "worth>100 id<=9", SortKey::Id, false), [9]);
+}
+
+#[test]
+fn sorting_breaks_ties_by_id() {
+    let sim = town();
+    assert_eq!(query(&sim, "", SortKey::Cash, true)[..3], [11, 10, 9]);
+    assert_eq!(query(&sim, "", SortKey::Utility, true), [0, 3, 6, 9, 1, 4, 7, 10, 2, 5, 8, 11]);
+    // Types sort by display name, so poor comes before rich.
+    assert_eq!(query(&sim, "id<4", SortKey::Type, false), [1, 3, 0, 2]);
+    assert_eq!(query(&sim, "cash<30", SortKey::NetWorth, true), [2, 1, 0]);
+    assert_eq!(SortKey::NetWorth.next(), SortKey::Id);
+}
+
+#[test]
+fn invalid_filters_are_rejected() {
+    let sim = town();
+    let err = |text: &str| Filter::parse(text, &sim.structure).unwrap_err().to_string();
+    assert!(err("type=middle").contains("unknown household type middle"));
+    assert!(err("type<poor").contains("type can only be compared with = or !="));
+    assert!(err("cash<lots").contains("lots is not a number"));
+    assert!(err("height>2").contains("unknown field height"));
+    assert!(err("cash").contains("has no comparison"));
+}
+
+#[test]
+fn search_finds_id_prefixes_in_display_order() {
+    let sim = town();
+    let rows = HouseholdQuery { filter: Filter::default(), sort: SortKey::Cash, descending: true }.run(&sim);
+    assert_eq!(search_id(&rows, "1"), Some(0));
+    assert_eq!(search_id(&rows, "10"), Some(1));
+    assert_eq!(search_id(&rows, "7"), Some(4));
+    assert_eq!(search_id(&rows, "42"), None);
+}
@@ -0,0 +1,175 @@
+//! Migrating a running simulation onto edited definitions.
+
+mod common;
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::{Overflow, Structure};
+use economy_sim::data::ids::{GoodId, HouseholdTypeId};
+use economy_sim::simulation::engine::Simulation;
+
+fn running(ticks: u64) -> Simulation {
+    let mut sim = Simulation::new(common::fixture("basic"), 7, 60);
+    for _ in 0..ticks {
+        sim.tick();
+    }
+    sim
+}
+
+fn good(s: &Structure, id: &str) -> GoodId {
+    GoodId(s.good_ids.map[id])
+}
+
+#[test]
+fn unchanged_data_keeps_the_state_hash() {
+    let mut sim = running(30);
+    let before = sim.state_hash();
+    let report = sim.migrate(common::fixture("basic")).unwrap();
+    assert!(report.added.is_empty() && report.removed.is_empty());
+    assert_eq!(sim.state_hash(), before);
+}
+
+#[test]
+fn reordered_and_added_entries_keep_holdings_by_id() {
+    let mut sim = running(30);
+    let bread_before: Vec<f64> = sim.households.iter().map(|h| h.good(good(&sim.structure, "bread"))).collect();
+    let bread_price = sim.market.price[good(&sim.structure, "bread").0 as usize];
+    let cash_before = common::total_cash(&sim);
+
+    let mut raw = common::raw_fixture("basic");
+    raw.goods.goods.reverse();
+    let mut b = StructureBuilder::from_raw(raw);
+    b.good("salt", 0.5);
+    b.stock("newco", 20.0, 1000);
+    let report = sim.migrate(b.build().unwrap()).unwrap();
+    assert_eq!(report.added, vec!["good salt", "stock newco"]);
+
+    let s = &sim.structure;
+    let bread = good(s, "bread");
+    assert_eq!(bread.0, 1, "bread moved after reversing the goods");
+    let bread_after: Vec<f64> = sim.households.iter().map(|h| h.good(bread)).collect();
+    assert_eq!(bread_after, bread_before);
+    assert_eq!(sim.market.price[bread.0 as usize], bread_price);
+    assert_eq!(sim.market.price[good(s, "salt").0 as usize], 0.5);
+    assert_eq!(sim.stock_market.price.len(), 3);
+    assert_eq!(common::total_cash(&sim), cash_before);
+
+    for _ in 0..50 {
+        common::tick_conserving_cash(&mut sim);
+        common::check_invariants(&sim);
+    }
+}
+
+#[test]
+fn edited_prices_and_intervals_apply_to_live_state() {
+    let mut sim = running(10);
+    let bread_price = sim.market.price[good(&sim.structure, "bread").0 as usize];
+
+    let mut raw = common::raw_fixture("basic");
+    raw.goods.goods.iter_mut().find(|g| g.id == "bread").unwrap().base_price = 10.0;
+    raw.needs.needs.iter_mut().find(|n| n.id == "need_bread").unwrap().interval_ticks = 1.into();
+    sim.migrate(Structure::compile(raw).unwrap()).unwrap();
+
+    let price = sim.market.price[good(&sim.structure, "bread").0 as usize];
+    assert!((price - bread_price * 2.0).abs() < 1e-9, "{} vs {}", price, bread_price);
+    common::check_invariants(&sim);
+}
+
+#[test]
+fn removed_goods_are_dropped_from_inventories() {
+    let mut sim = running(10);
+    let mut raw = common::raw_fixture("basic");
+    raw.goods.goods.retain(|g| g.id != "bread");
+    raw.needs.needs.retain(|n| n.id != "need_bread");
+    for t in &mut raw.household_types.types {
+        t.starting_inventory.items.retain(|i| i.good_ref != "bread");
+        t.needs.need_refs.retain(|n| n != "need_bread");
+    }
+    raw.production.rules.clear();
+    let report = sim.migrate(Structure::compile(raw).unwrap()).unwrap();
+    assert!(report.removed.contains(&"good bread".to_string()));
+    assert!(report.removed.contains(&"need need_bread".to_string()));
+    assert_eq!(sim.market.price.len(), 1);
+    assert!(sim.households.iter().all(|h| h.inventory.len() == 1));
+
+    for _ in 0..20 {
+        common::tick_conserving_cash(&mut sim);
+        common::check_invariants(&sim);
+    }
+}
+
+#[test]
+fn holdings_over_a_reduced_capacity_are_dropped() {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 1.0).volume(1.0);
+    b.good("salt", 1.0).volume(1.0);
+    b.household_type("cook", 0.0).inventory("bread", 4.0).inventory("salt", 4.0).storage(10.0, 0.0, Overflow::Refuse);
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 2);
+
+    let mut raw = b.into_raw();
+    let cook = &mut raw.household_types.types[0];
+    cook.storage.as_mut().unwrap().capacity = 6.0;
+    cook.starting_inventory.items[1].qty = 2.0;
+    let report = sim.migrate(Structure::compile(raw).unwrap()).unwrap();
+    // Goods are stored again in id order, so salt is what no longer fits.
+    assert_eq!(report.notes, ["4.0 units over storage capacity dropped from inventories"]);
+    assert_eq!(sim.households.inventory, [4.0, 2.0, 4.0, 2.0]);
+}
+
+#[test]
+fn inspected_households_start_a_new_timeline() {
+    let mut sim = Simulation::new(common::fixture("basic"), 7, 60);
+    let trader = HouseholdTypeId(sim.structure.household_type_ids.map["trader"]);
+    let watched = sim.households.iter().find(|h| h.kind == trader).unwrap().id;
+    sim.inspect(Some(watched));
+    for _ in 0..30 {
+        sim.tick();
+    }
+    assert!(!sim.inspector.as_ref().unwrap().timeline.is_empty());
+
+    // Removing bread and reordering what is left would leave the timeline
+    // naming goods by ids that are gone or mean something else.
+    let mut raw = common::raw_fixture("basic");
+    raw.goods.goods.retain(|g| g.id != "bread");
+    raw.goods.goods.reverse();
+    raw.needs.needs.retain(|n| n.id != "need_bread");
+    for t in &mut raw.household_types.types {
+        t.starting_inventory.items.retain(|i| i.good_ref != "bread");
+        t.needs.need_refs.retain(|n| n != "need_bread");
+    }
+    raw.production.rules.clear();
+    sim.migrate(Structure::compile(raw).unwrap()).unwrap();
+    let i = sim.inspector.as_ref().unwrap();
+    assert_eq!((i.household, i.cash.len(), i.utility.len()), (watched, 30, 30));
+    assert!(i.timeline.is_empty() && i.streaks.is_empty());
+
+    for _ in 0..30 {
+        sim.tick();
+    }
+    let i = sim.inspector.as_ref().unwrap();
+    assert_eq!(i.streaks.len(), sim.structure.household_types[sim.households.get(sim.households.position(watched).unwrap()).kind.0 as usize].needs.len());
+    for (_, a) in &i.timeline {
+        a.describe(&sim.structure);
+    }
+}
+
+#[test]
+fn incompatible_changes_are_reported_and_leave_the_simulation_alone() {
+    let mut sim = running(10);
+    let before = sim.state_hash();
+    let mut raw = common::raw_fixture("basic");
+    raw.stocks.stocks.retain(|s| s.id != "steady");
+    for t in &mut raw.household_types.types {
+        t.starting_portfolio.items.retain(|i| i.stock_ref != "steady");
+    }
+    raw.stocks.indices.clear();
+    for t in &mut raw.household_types.types {
+        t.index_ref = None;
+    }
+    raw.household_types.types.retain(|t| t.id != "trader");
+
+    let err = sim.migrate(Structure::compile(raw).unwrap()).unwrap_err().to_string();
+    assert!(err.contains("household_type trader removed"), "{}", err);
+    assert!(err.contains("stock steady removed"), "{}", err);
+    assert_eq!(sim.state_hash(), before);
+    assert_eq!(sim.structure.stocks.len(), 2);
+}
@@ -0,0 +1,60 @@
+//! Recorded sessions replay to the same state hashes, in the library and
+//! through `--replay`.
+
+use economy_sim::config::Config;
+use economy_sim::data;
+use economy_sim::replay::{self, Input, ReplayLog};
+use economy_sim::simulation::engine::Simulation;
+use std::process::Command;
+
+const CONFIG: &str = "tests/fixtures/golden/config.toml";
+
+/// Plays a session the way the TUI records one: each input is logged with the
+/// hash before it, while the simulation runs freely in between.
+fn record() -> ReplayLog {
+    let cfg = Config::load(Some(CONFIG)).unwrap();
+    let mut sim = Simulation::new(data::load_and_compile(&cfg).unwrap(), 7, 60);
+    let mut log = ReplayLog::new(7, 60);
+    let script = [(12, Input::ForceSell), (20, Input::Pause), (20, Input::Step), (20, Input::Step), (20, Input::Resume), (31, Input::Reset)];
+    for (tick, input) in script {
+        while sim.tick < tick {
+            sim.tick();
+        }
+        log.push(sim.tick, input, sim.state_hash());
+        input.apply(&mut sim, 7, 60);
+    }
+    for _ in 0..15 {
+        sim.tick();
+    }
+    log.finish(sim.tick, sim.state_hash());
+    log
+}
+
+#[test]
+fn replays_reproduce_the_recorded_hashes() {
+    let log = record();
+    let parsed = ReplayLog::parse(&log.to_string()).unwrap();
+    assert_eq!(parsed.entries.len(), 6);
+    let cfg = Config::load(Some(CONFIG)).unwrap();
+    assert_eq!(Some(replay::run(data::load_and_compile(&cfg).unwrap(), &parsed).unwrap()), log.end);
+
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("session.replay");
+    log.save(path.to_str().unwrap()).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_EconomySim"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--config", CONFIG, "--replay", path.to_str().unwrap()])
+        .output()
+        .expect("running EconomySim --replay");
+    assert!(out.status.success(), "replay failed: {}", String::from_utf8_lossy(&out.stderr));
+    let (tick, hash) = log.end.unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), format!("replay ok: 6 inputs, tick {}, hash {:016x}", tick, hash));
+}
+
+#[test]
+fn diverging_replays_name_the_input() {
+    let mut log = record();
+    log.entries[1].hash ^= 1;
+    let cfg = Config::load(Some(CONFIG)).unwrap();
+    let err = replay::run(data::load_and_compile(&cfg).unwrap(), &log).unwrap_err().to_string();
+    assert!(err.starts_with("replay diverged before pause at tick 20"), "{}", err);
+}
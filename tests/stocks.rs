@@ -0,0 +1,103 @@
+//! Dividends, share float and corporate actions.
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::CorporateActionKind;
+use economy_sim::data::ids::StockId;
+use economy_sim::simulation::engine::Simulation;
+
+/// Households too poor to trade holding `shares` of a stock that pays half its
+/// earnings of 1 every 5 ticks.
+fn exchange(shares: f64) -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 1.0);
+    b.stock("acme", 10.0, 1000).earnings(1.0, 0.0, 0.0).dividends(0.5, 5);
+    b.household_type("holder", 100.0).shares("acme", shares);
+    b
+}
+
+#[test]
+fn dividends_are_paid_per_share_held() {
+    let mut sim = Simulation::new(exchange(10.0).build().unwrap(), 1, 1);
+    for _ in 0..5 {
+        sim.tick();
+    }
+    assert_eq!(sim.households.cash[0], 105.0);
+    assert_eq!(sim.cash_flows.dividends, 5.0);
+    assert_eq!(sim.stock_market.last_dividend[0], 0.5);
+}
+
+#[test]
+fn unlisted_stocks_pay_no_dividends() {
+    let mut b = exchange(0.0);
+    b.stock("late", 10.0, 0).earnings(1.0, 0.0, 0.0).dividends(0.5, 5).corporate_action(CorporateActionKind::Ipo, 10, 1000.0);
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 1);
+    // However the shares got there, nothing is paid on them before the listing.
+    sim.households.portfolio[1] = 10.0;
+    for _ in 0..5 {
+        sim.tick();
+    }
+    assert!(!sim.stock_market.listed[1]);
+    assert_eq!(sim.stock_market.dividend_due[1], 0.0);
+    assert_eq!(sim.households.cash[0], 100.0);
+    for _ in 0..5 {
+        sim.tick();
+    }
+    assert!(sim.stock_market.listed[1]);
+    assert_eq!(sim.households.cash[0], 105.0);
+    assert_eq!(sim.stock_market.dividend_yield(StockId(1)), 0.5 / sim.stock_market.price[1]);
+}
+
+#[test]
+fn starting_portfolios_come_out_of_the_float() {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 1.0);
+    b.stock("acme", 10.0, 100);
+    b.stock("late", 10.0, 0).corporate_action(CorporateActionKind::Ipo, 10, 100.0);
+    b.household_type("holder", 100.0).shares("acme", 40.0).shares("late", 40.0);
+    let sim = Simulation::new(b.build().unwrap(), 1, 3);
+    let acme: Vec<f64> = (0..3).map(|i| sim.households.get(i).shares(StockId(0))).collect();
+    assert_eq!(acme, [40.0, 40.0, 20.0]);
+    assert_eq!(sim.stock_market.available(StockId(0)), 0.0);
+    // Nothing is handed out before the listing.
+    assert!((0..3).all(|i| sim.households.get(i).shares(StockId(1)) == 0.0));
+    assert_eq!(sim.stock_market.held[1], 0.0);
+}
+
+#[test]
+fn short_sales_lend_out_held_shares() {
+    let mut sim = Simulation::new(exchange(10.0).build().unwrap(), 1, 10);
+    let acme = StockId(0);
+    let sm = &mut sim.stock_market;
+    assert_eq!((sm.available(acme), sm.lendable(acme)), (900.0, 100.0));
+    sm.note_short(acme, 30.0);
+    // Borrowed shares sold short add to what can be bought.
+    assert_eq!((sm.available(acme), sm.lendable(acme)), (930.0, 70.0));
+    sm.note_cover(acme, 10.0);
+    assert_eq!((sm.available(acme), sm.lendable(acme)), (920.0, 80.0));
+    sm.note_buy(acme, 920.0);
+    assert_eq!((sm.available(acme), sm.lendable(acme)), (0.0, 1000.0));
+}
+
+#[test]
+fn buybacks_pay_the_holders() {
+    let buy_back = |shares: f64| {
+        let mut b = exchange(10.0);
+        b.stock("acme", 10.0, 1000).corporate_action(CorporateActionKind::Buyback, 1, shares);
+        let mut raw = b.into_raw();
+        raw.stocks.stocks.remove(0);
+        let mut sim = Simulation::new(StructureBuilder::from_raw(raw).build().unwrap(), 1, 2);
+        sim.tick();
+        sim
+    };
+
+    let sim = buy_back(5.0);
+    assert_eq!(sim.households.cash, [125.0, 125.0]);
+    assert_eq!(sim.households.portfolio, [7.5, 7.5]);
+    assert_eq!(sim.cash_flows.buybacks, 50.0);
+    assert_eq!((sim.stock_market.held[0], sim.stock_market.treasury[0]), (15.0, 5.0));
+
+    // Beyond what households hold, the rest comes from the unheld float.
+    let sim = buy_back(30.0);
+    assert_eq!(sim.cash_flows.buybacks, 200.0);
+    assert_eq!(sim.stock_market.treasury[0], 30.0);
+}
@@ -0,0 +1,91 @@
+//! Storage capacity, storage costs, overflow handling and whole-unit goods.
+
+use economy_sim::data::builder::StructureBuilder;
+use economy_sim::data::compiled::{GoodCategory, Overflow, Structure};
+use economy_sim::data::ids::GoodId;
+use economy_sim::simulation::engine::Simulation;
+
+/// A cook holding 8 bread in a pantry of 10 who wants 5 more.
+fn pantry(overflow: Overflow, cost_per_volume: f64) -> StructureBuilder {
+    let mut b = StructureBuilder::new();
+    b.good("bread", 2.0).volume(1.0);
+    b.need("need_bread", "bread", 5.0, 0);
+    b.household_type("cook", 100.0).inventory("bread", 8.0).need("need_bread").storage(10.0, cost_per_volume, overflow);
+    b
+}
+
+/// Buys the cook's unmet needs at the base price; returns (cash, bread, supply).
+fn buy(overflow: Overflow) -> (f64, f64, f64) {
+    let mut sim = Simulation::new(pantry(overflow, 0.0).build().unwrap(), 1, 1);
+    let (mut demand, mut supply) = (vec![0.0], vec![0.0]);
+    let mut h = sim.households.get_mut(0);
+    h.fulfilled_last[0] = false;
+    h.buy_unmet_needs(&sim.structure, &[2.0], &mut demand, &mut supply);
+    assert_eq!(demand, [5.0]);
+    let h = sim.households.get(0);
+    (h.cash, h.good(GoodId(0)), supply[0])
+}
+
+#[test]
+fn overflow_modes() {
+    // Only 2 of the 5 fit.
+    assert_eq!(buy(Overflow::Refuse), (96.0, 10.0, 0.0));
+    assert_eq!(buy(Overflow::Discard), (90.0, 10.0, 0.0));
+    assert_eq!(buy(Overflow::Sell), (96.0, 10.0, 3.0));
+}
+
+#[test]
+fn store_reports_what_did_not_fit() {
+    let mut sim = Simulation::new(pantry(Overflow::Refuse, 0.0).build().unwrap(), 1, 1);
+    let mut h = sim.households.get_mut(0);
+    assert_eq!(h.store(&sim.structure, GoodId(0), 1.5), 0.0);
+    assert_eq!(h.store(&sim.structure, GoodId(0), 1.5), 1.0);
+    assert_eq!(h.volume_used(&sim.structure), 10.0);
+}
+
+#[test]
+fn storage_costs_are_recorded() {
+    let mut sim = Simulation::new(pantry(Overflow::Refuse, 0.01).build().unwrap(), 1, 1);
+    sim.tick();
+    assert!((sim.cash_flows.storage + 0.08).abs() < 1e-12, "storage {}", sim.cash_flows.storage);
+    assert!((sim.households.cash[0] - 99.92).abs() < 1e-12);
+}
+
+#[test]
+fn worn_non_stackable_goods_are_replaced_whole() {
+    let mut b = StructureBuilder::new();
+    b.good("fridge", 100.0).category(GoodCategory::Durable).decay_rate(0.2).stackable(false).volume(10.0);
+    b.need("need_fridge", "fridge", 1.0, 0);
+    b.household_type("cook", 1000.0).inventory("fridge", 1.0).need("need_fridge").storage(20.0, 0.0, Overflow::Refuse);
+    let mut sim = Simulation::new(b.build().unwrap(), 1, 1);
+    // A worn fridge is still a fridge, so nothing is bought while it lasts.
+    for left in [0.8, 0.64, 0.512] {
+        sim.tick();
+        let h = sim.households.get(0);
+        assert!((h.good(GoodId(0)) - left).abs() < 1e-12);
+        assert_eq!((h.cash, h.fulfilled_last[0]), (1000.0, true));
+    }
+    // Worn past half, it is thrown out and a whole new one bought in its place.
+    sim.tick();
+    let h = sim.households.get(0);
+    assert_eq!((h.cash, h.good(GoodId(0)), h.fulfilled_last[0]), (900.0, 1.0, false));
+    assert_eq!(h.volume_used(&sim.structure), 10.0);
+}
+
+#[test]
+fn invalid_storage_is_rejected() {
+    let err = |b: StructureBuilder| b.build().unwrap_err().to_string();
+
+    let mut b = pantry(Overflow::Refuse, 0.0);
+    b.household_type("hoarder", 0.0).inventory("bread", 11.0).storage(10.0, 0.0, Overflow::Sell);
+    assert!(err(b).contains("starting inventory takes 11 of its storage capacity 10"));
+
+    let mut b = StructureBuilder::new();
+    b.good("fridge", 100.0).stackable(false);
+    b.household_type("cook", 0.0).inventory("fridge", 0.5);
+    assert!(err(b).contains("household_type cook starts with a fraction of non-stackable good fridge"));
+
+    let mut raw = pantry(Overflow::Refuse, 0.0).into_raw();
+    raw.household_types.types[0].storage.as_mut().unwrap().overflow = "burn".to_string();
+    assert!(Structure::compile(raw).unwrap_err().to_string().contains("unknown overflow burn"));
+}
@@ -0,0 +1,115 @@
+//! Sweep specs, numeric overrides and the sweep CSV.
+
+mod common;
+
+use common::{fixture_config, raw_fixture};
+use economy_sim::data::compiled::{CorporateActionKind, Structure};
+use economy_sim::data::overrides::set_numeric;
+use economy_sim::simulation::economy::EconomyMetrics;
+use economy_sim::simulation::engine::Simulation;
+use economy_sim::sweep::{self, ParamValue, SweepSpec};
+
+#[test]
+fn specs_expand_to_every_combination() {
+    let spec = SweepSpec::parse(
+        r#"
+        ticks = 10
+
+        [params]
+        seed = [9007199254740993, 2]
+        start_households = { from = 10, to = 30, step = 10 }
+        "goods.bread.base_price" = { from = 0.5, to = 1.0, step = 0.25 }
+        "#,
+    )
+    .unwrap();
+    let runs = spec.runs().unwrap();
+    assert_eq!(runs.len(), 18);
+    // Keys are in order, the last one varying fastest.
+    let first: Vec<(&str, ParamValue)> = runs[0].iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    assert_eq!(first, [
+        ("goods.bread.base_price", ParamValue::Float(0.5)),
+        ("seed", ParamValue::Int(9007199254740993)),
+        ("start_households", ParamValue::Int(10)),
+    ]);
+    assert_eq!(runs[1][2].1, ParamValue::Int(20));
+    assert_eq!(runs[3][1].1, ParamValue::Int(2));
+    assert_eq!(runs[17][0].1, ParamValue::Float(1.0));
+}
+
+#[test]
+fn invalid_specs_are_rejected() {
+    let runs = |params: &str| SweepSpec::parse(&format!("ticks = 1\n[params]\n{}", params)).unwrap().runs();
+    assert!(runs("seed = []").unwrap_err().to_string().contains("seed has no values"));
+    assert!(runs("seed = { from = 3, to = 1, step = 1 }").unwrap_err().to_string().contains("needs from <= to"));
+    assert!(runs("\"goods.bread.volume\" = { from = 0.0, to = 1.0, step = 0.0 }").is_err());
+
+    let spec = SweepSpec::parse("ticks = 1\n[params]\nseed = [1.5]").unwrap();
+    let err = format!("{:#}", sweep::run(&fixture_config("basic"), &spec, 1).unwrap_err());
+    assert!(err.contains("seed must be a non-negative whole number, got 1.5"), "{}", err);
+}
+
+#[test]
+fn overrides_reach_every_section() {
+    let mut raw = raw_fixture("basic");
+    for (key, value) in [
+        ("margin.initial_margin", 0.8),
+        ("margin.loan_rate_per_tick", 0.01),
+        ("insolvency.grace_ticks", 5.0),
+        ("insolvency.debt_write_down", 0.5),
+        ("firm_insolvency.recovery_rate", 0.4),
+        ("corporate_actions.steady.split_ratio", 2.0),
+        ("corporate_actions.steady.buyback_tick", 120.0),
+        ("indices.all.base_value", 100.0),
+    ] {
+        set_numeric(&mut raw, key, value).unwrap();
+    }
+    let s = Structure::compile(raw.clone()).unwrap();
+    assert_eq!((s.margin.initial_margin, s.margin.loan_rate_per_tick), (0.8, 0.01));
+    let ins = s.insolvency.as_ref().unwrap();
+    assert_eq!((ins.grace_ticks, ins.debt_write_down), (5, 0.5));
+    assert_eq!(s.firm_insolvency.as_ref().unwrap().recovery_rate, 0.4);
+    let actions = &s.stocks[0].corporate_actions;
+    assert!(actions.iter().any(|a| a.kind == CorporateActionKind::Split && a.ratio == 2.0));
+    assert!(actions.iter().any(|a| a.kind == CorporateActionKind::Buyback && a.tick == 120));
+    assert_eq!(s.indices[0].base_value, 100.0);
+
+    let mut err = |key: &str| set_numeric(&mut raw, key, 1.0).unwrap_err().to_string();
+    assert!(err("corporate_actions.bust.split_ratio").contains("stock bust has no split action"));
+    assert!(err("corporate_actions.steady.split_volume").contains("has no numeric field split_volume"));
+    assert!(err("indices.none.base_value").contains("no indices with id none"));
+    assert!(err("goods.base_price").contains("must look like <category>.<id>.<field>"));
+    assert!(err("weather.rain").contains("unknown category weather"));
+}
+
+#[test]
+fn rows_match_direct_runs_for_any_thread_count() {
+    let cfg = fixture_config("basic");
+    let spec = SweepSpec::parse(
+        r#"
+        ticks = 20
+        sample_every = 10
+
+        [params]
+        seed = [3, 4]
+        "household_types.poor.income_per_tick" = [0.5]
+        "#,
+    )
+    .unwrap();
+    let csv = sweep::run(&cfg, &spec, 1).unwrap();
+    assert_eq!(csv, sweep::run(&cfg, &spec, 3).unwrap());
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("run_id,household_types.poor.income_per_tick,seed,tick,households,total_cash,"));
+    assert!(lines[1].starts_with("0,0.5,3,10,"));
+    assert!(lines[4].starts_with("1,0.5,4,20,"));
+
+    let mut raw = raw_fixture("basic");
+    set_numeric(&mut raw, "household_types.poor.income_per_tick", 0.5).unwrap();
+    let mut sim = Simulation::new(Structure::compile(raw).unwrap(), 4, cfg.start_households);
+    for _ in 0..20 {
+        sim.tick();
+    }
+    let m = EconomyMetrics::compute(&sim);
+    assert!(lines[4].starts_with(&format!("1,0.5,4,20,{},{},{},", m.households, m.total_cash, m.avg_utility)), "{}", lines[4]);
+}